@@ -0,0 +1,285 @@
+use geometry::matrix::Matrix44;
+use geometry::vector::{Vec3, Vec4};
+
+/* Near and far planes of the frustum built by Camera::new. Lines are
+   clipped against the near plane, which at distance 1 puts the canvas
+   exactly on the image plane; the far plane only affects the depth in NDC. */
+const DEFAULT_NEAR: f64 = 1.0;
+const DEFAULT_FAR: f64 = 1000.0;
+
+/// A camera made of its placement in the world and a projection matrix that
+/// takes camera space to clip space.
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub camera_to_world: Matrix44<f64>,
+    pub world_to_camera: Matrix44<f64>,
+    pub projection: Matrix44<f64>,
+}
+
+impl Camera {
+    /// A pinhole camera with a `canvas_width` by `canvas_height` canvas on
+    /// its image plane at distance 1 in front of it.
+    pub fn new(camera_to_world: Matrix44<f64>, canvas_width: f64, canvas_height: f64) -> Result<Camera, String> {
+        let right = canvas_width * 0.5 * DEFAULT_NEAR;
+        let top = canvas_height * 0.5 * DEFAULT_NEAR;
+        let projection = Matrix44::new_frustum(-right, right, -top, top, DEFAULT_NEAR, DEFAULT_FAR);
+
+        Camera::with_projection(camera_to_world, projection)
+    }
+
+    /// A pinhole camera that sees `field_of_view` radians across the
+    /// narrower side of an `image_width` by `image_height` image.
+    pub fn with_field_of_view(camera_to_world: Matrix44<f64>, field_of_view: f64, image_width: u32, image_height: u32) -> Result<Camera, String> {
+        if !(field_of_view > 0.0 && field_of_view < std::f64::consts::PI) {
+            return Err(format!("Invalid camera: the field of view has to be between 0 and 180 degrees, not {}", field_of_view.to_degrees()));
+        }
+
+        let side = 2.0 * (field_of_view / 2.0).tan();
+        let (canvas_width, canvas_height) = fit_canvas(side, side, image_width, image_height);
+        Camera::new(camera_to_world, canvas_width, canvas_height)
+    }
+
+    /// A camera using any projection, such as one built with
+    /// `Matrix44::new_perspective` or `Matrix44::new_orthographic`.
+    ///
+    /// Fails when `camera_to_world` can't be inverted, for example when it
+    /// scales an axis to zero or holds NaN values.
+    pub fn with_projection(camera_to_world: Matrix44<f64>, projection: Matrix44<f64>) -> Result<Camera, String> {
+        let world_to_camera = match camera_to_world.try_inverse() {
+            Some(inverse) => inverse,
+            None => return Err(format!("Invalid camera: the camera to world matrix is singular and can't be inverted: {:?}", camera_to_world)),
+        };
+
+        Ok(Camera{camera_to_world, world_to_camera, projection})
+    }
+
+    /// Takes a point in world space to clip space, before the perspective
+    /// divide. Points inside the view volume have x, y and z between -w and w.
+    pub fn clip(&self, p_world: &Vec3<f64>) -> Vec4<f64> {
+        /* Take the point in the world coordinate system and and translate
+           it into the camera coordinate system. */
+        let p_camera = self.world_to_camera.multVecMatrix(p_world);
+
+        self.projection * Vec4::from_point(p_camera)
+    }
+
+    /// Projects a point in world space to raster space, for an image of
+    /// `image_width` by `image_height` pixels. Points in front of the near
+    /// plane, which includes those behind the camera, give `None`.
+    pub fn project(&self, p_world: &Vec3<f64>, image_width: u32, image_height: u32) -> Option<(f64, f64)> {
+        let p_clip = self.clip(p_world);
+        if near_plane_distance(p_clip) < 0.0 {
+            return None;
+        }
+
+        Some(clip_to_raster(p_clip, image_width, image_height))
+    }
+
+    /// The point in world space that `p_world` is seen from: the camera's
+    /// position, or for projections without perspective, the point on the
+    /// camera's plane straight behind `p_world`.
+    pub fn viewpoint(&self, p_world: &Vec3<f64>) -> Vec3<f64> {
+        let p_camera = self.world_to_camera.multVecMatrix(p_world);
+        let viewpoint = if self.projection[(3, 3)] == 0.0 {
+            Vec3::new(0.0, 0.0, 0.0)
+        } else {
+            Vec3::new(p_camera.x, p_camera.y, 0.0)
+        };
+
+        self.camera_to_world.multVecMatrix(&viewpoint)
+    }
+
+    /// Projects the line from `a` to `b` like `project`, cutting off the
+    /// part in front of the near plane. Gives `None` when nothing is left.
+    pub fn project_line(&self, a: &Vec3<f64>, b: &Vec3<f64>, image_width: u32, image_height: u32) -> Option<((f64, f64), (f64, f64))> {
+        let (a_clip, b_clip) = clip_to_near_plane(self.clip(a), self.clip(b))?;
+
+        Some((clip_to_raster(a_clip, image_width, image_height), clip_to_raster(b_clip, image_width, image_height)))
+    }
+}
+
+/* How far inside the near plane, z = -w, a point in clip space is. Points
+   on the plane come out of the projection matrix a rounding error off, so
+   they are moved inside by a little more than that. */
+fn near_plane_distance(p_clip: Vec4<f64>) -> f64 {
+    p_clip.z + p_clip.w + 1e-9 * p_clip.w.abs()
+}
+
+/* Clips a line in clip space against the near plane. This has to happen
+   before the perspective divide, which flips points behind the camera to
+   the other side. */
+fn clip_to_near_plane(a: Vec4<f64>, b: Vec4<f64>) -> Option<(Vec4<f64>, Vec4<f64>)> {
+    let (a_distance, b_distance) = (near_plane_distance(a), near_plane_distance(b));
+    if a_distance >= 0.0 && b_distance >= 0.0 {
+        return Some((a, b));
+    }
+    if !(a_distance >= 0.0 || b_distance >= 0.0) {
+        return None;
+    }
+
+    let crossing = a + (b - a) * (a_distance / (a_distance - b_distance));
+    if a_distance < 0.0 {
+        Some((crossing, b))
+    } else {
+        Some((a, crossing))
+    }
+}
+
+/* Divides by w to go from clip space to NDC, and on to raster space. */
+fn clip_to_raster(p_clip: Vec4<f64>, image_width: u32, image_height: u32) -> (f64, f64) {
+    let p_ndc = p_clip.perspective_divide();
+
+    ndc_to_raster(p_ndc.x, p_ndc.y, image_width, image_height)
+}
+
+/// Grows the canvas along one axis so that it has the aspect ratio of an
+/// `image_width` by `image_height` image. Everything on the requested canvas
+/// stays in view and pixels stay square, so wide images aren't stretched.
+pub fn fit_canvas(canvas_width: f64, canvas_height: f64, image_width: u32, image_height: u32) -> (f64, f64) {
+    if image_width == 0 || image_height == 0 {
+        return (canvas_width, canvas_height);
+    }
+
+    let image_aspect = image_width as f64 / image_height as f64;
+    if canvas_width / canvas_height < image_aspect {
+        (canvas_height * image_aspect, canvas_height)
+    } else {
+        (canvas_width, canvas_width / image_aspect)
+    }
+}
+
+/// Converts a point in normalized device coordinates, where x and y go from
+/// -1 to 1 with y pointing up, into the raster coordinate system, where y
+/// points down.
+pub fn ndc_to_raster(x: f64, y: f64, image_width: u32, image_height: u32) -> (f64, f64) {
+    let p_raster_x = (x + 1.0) * 0.5 * image_width as f64;
+    let p_raster_y = (1.0 - y) * 0.5 * image_height as f64;
+
+    (p_raster_x, p_raster_y)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use geometry::matrix::Matrix44;
+    use geometry::vector::Vec3;
+    use super::{Camera, fit_canvas, ndc_to_raster};
+
+    #[test]
+    fn project_should_map_points_onto_the_raster() {
+        let identity: Matrix44<f64> = Matrix44::new(1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+        let camera = Camera::new(identity, 2.0, 2.0).unwrap();
+
+        let (x, y) = camera.project(&Vec3::new(0.0, 0.0, -1.0), 512, 512).unwrap();
+        assert_delta!(x, 256.0, 0.001);
+        assert_delta!(y, 256.0, 0.001);
+
+        let (x, y) = camera.project(&Vec3::new(-2.0, 2.0, -2.0), 512, 256).unwrap();
+        assert_delta!(x, 0.0, 0.001);
+        assert_delta!(y, 0.0, 0.001);
+    }
+
+    #[test]
+    fn project_should_use_the_inverse_of_the_camera_to_world_matrix() {
+        let camera_to_world: Matrix44<f64> = Matrix44::new(0.871214, 0.0, -0.490904, 0.0, -0.192902, 0.919559, -0.342346, 0.0, 0.451415, 0.392953, 0.801132, 0.0, 14.777467, 29.361945, 27.993464, 1.0);
+        let camera = Camera::new(camera_to_world, 2.0, 2.0).unwrap();
+
+        let (x, y) = camera.project(&Vec3::new(0.0, 39.033714, 0.0), 512, 512).unwrap();
+        assert_delta!(x, 264.78, 0.01);
+        assert_delta!(y, 40.16, 0.01);
+    }
+
+    #[test]
+    fn project_should_use_the_projection_matrix() {
+        let identity: Matrix44<f64> = Matrix44::new(1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+        let orthographic = Camera::with_projection(identity, Matrix44::new_orthographic(-4.0, 4.0, -4.0, 4.0, 0.1, 100.0)).unwrap();
+
+        /* No perspective: depth doesn't move the point. */
+        let (x, y) = orthographic.project(&Vec3::new(2.0, 2.0, -50.0), 100, 100).unwrap();
+        assert_delta!(x, 75.0, 0.001);
+        assert_delta!(y, 25.0, 0.001);
+
+        /* A 90 degree field of view is the same as a 2 by 2 canvas. */
+        let perspective = Camera::with_projection(identity, Matrix44::new_perspective(90.0_f64.to_radians(), 1.0, 0.1, 100.0)).unwrap();
+        let pinhole = Camera::new(identity, 2.0, 2.0).unwrap();
+        let p = Vec3::new(0.3, -0.7, -2.5);
+        let (x1, y1) = perspective.project(&p, 512, 512).unwrap();
+        let (x2, y2) = pinhole.project(&p, 512, 512).unwrap();
+        assert_delta!(x1, x2, 0.001);
+        assert_delta!(y1, y2, 0.001);
+    }
+
+    #[test]
+    fn clip_should_keep_the_depth_in_w() {
+        let identity: Matrix44<f64> = Matrix44::new_identity();
+        let camera = Camera::new(identity, 2.0, 2.0).unwrap();
+
+        let p = camera.clip(&Vec3::new(1.0, -3.0, -5.0));
+        assert_delta!(p.x, 1.0, 0.001);
+        assert_delta!(p.y, -3.0, 0.001);
+        assert_delta!(p.w, 5.0, 0.001);
+        /* Behind the camera, w is negative. */
+        assert!(camera.clip(&Vec3::new(0.0, 0.0, 2.0)).w < 0.0);
+    }
+
+    #[test]
+    fn project_line_should_clip_lines_against_the_near_plane() {
+        let identity: Matrix44<f64> = Matrix44::new_identity();
+        let camera = Camera::new(identity, 2.0, 2.0).unwrap();
+        let in_front = Vec3::new(1.0, 0.0, -3.0);
+        let behind = Vec3::new(-1.0, 0.0, 2.0);
+
+        assert_eq!(None, camera.project(&behind, 100, 100));
+
+        /* The line crosses the near plane at x = 0.2, z = -1. */
+        let ((x1, y1), (x2, y2)) = camera.project_line(&in_front, &behind, 100, 100).unwrap();
+        assert_delta!(x1, 66.667, 0.001);
+        assert_delta!(y1, 50.0, 0.001);
+        assert_delta!(x2, 60.0, 0.001);
+        assert_delta!(y2, 50.0, 0.001);
+
+        let ((x1, _), (x2, _)) = camera.project_line(&behind, &Vec3::new(-1.0, 0.0, -2.0), 100, 100).unwrap();
+        assert_delta!(x1, 0.0, 0.001);
+        assert_delta!(x2, 25.0, 0.001);
+
+        assert_eq!(None, camera.project_line(&behind, &Vec3::new(1.0, 0.0, 0.5), 100, 100));
+    }
+
+    #[test]
+    fn fit_canvas_should_grow_the_canvas_to_the_image_aspect_ratio() {
+        assert_eq!((2.0, 2.0), fit_canvas(2.0, 2.0, 512, 512));
+        assert_eq!((4.0, 2.0), fit_canvas(2.0, 2.0, 1024, 512));
+        assert_eq!((2.0, 4.0), fit_canvas(2.0, 2.0, 256, 512));
+        assert_eq!((4.0, 2.0), fit_canvas(4.0, 1.0, 200, 100));
+    }
+
+    #[test]
+    fn with_field_of_view_should_not_stretch_wide_images() {
+        let identity: Matrix44<f64> = Matrix44::new_identity();
+        let camera = Camera::with_field_of_view(identity, 90.0_f64.to_radians(), 200, 100).unwrap();
+
+        /* A square one unit away spans the full height and half the width. */
+        let (x1, y1) = camera.project(&Vec3::new(-1.0, 1.0, -1.0), 200, 100).unwrap();
+        let (x2, y2) = camera.project(&Vec3::new(1.0, -1.0, -1.0), 200, 100).unwrap();
+        assert_delta!(x2 - x1, 100.0, 0.001);
+        assert_delta!(y2 - y1, 100.0, 0.001);
+
+        assert!(Camera::with_field_of_view(identity, 180.0_f64.to_radians(), 200, 100).is_err());
+    }
+
+    #[test]
+    fn ndc_to_raster_should_flip_the_y_axis() {
+        assert_eq!((0.0, 0.0), ndc_to_raster(-1.0, 1.0, 640, 480));
+        assert_eq!((640.0, 480.0), ndc_to_raster(1.0, -1.0, 640, 480));
+        assert_eq!((320.0, 240.0), ndc_to_raster(0.0, 0.0, 640, 480));
+    }
+
+    #[test]
+    fn new_should_fail_for_a_degenerate_camera_matrix() {
+        let flat: Matrix44<f64> = Matrix44::new_scaling(1.0, 1.0, 0.0);
+        assert!(Camera::new(flat, 2.0, 2.0).unwrap_err().contains("singular"));
+
+        let nan: Matrix44<f64> = Matrix44::new_translation(0.0, f64::NAN, 0.0);
+        assert!(Camera::new(nan, 2.0, 2.0).is_err());
+    }
+}
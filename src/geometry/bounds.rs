@@ -0,0 +1,327 @@
+extern crate num;
+
+use geometry::matrix::*;
+use geometry::vector::*;
+use wavefront::object_parser::{Object, Triangle, Vertex};
+
+/* An axis-aligned bounding box, from its minimum corner to its maximum. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb<T: num::Float> {
+    pub min: Vec3<T>,
+    pub max: Vec3<T>,
+}
+
+impl<T: num::Float> Aabb<T> {
+    pub fn new(min: Vec3<T>, max: Vec3<T>) -> Aabb<T> {
+        Aabb{min, max}
+    }
+
+    /* None when there are no points. */
+    pub fn from_points<I: IntoIterator<Item = Vec3<T>>>(points: I) -> Option<Aabb<T>> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let mut aabb = Aabb::new(first, first);
+        for p in points {
+            aabb.extend(p);
+        }
+
+        Some(aabb)
+    }
+
+    pub fn extend(&mut self, p: Vec3<T>) {
+        self.min = self.min.min(p);
+        self.max = self.max.max(p);
+    }
+
+    pub fn union(&self, other: &Aabb<T>) -> Aabb<T> {
+        Aabb::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn center(&self) -> Vec3<T> {
+        self.min.lerp(self.max, num::cast::<f64, T>(0.5).unwrap())
+    }
+
+    pub fn size(&self) -> Vec3<T> {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> T {
+        let two: T = num::cast::<f64, T>(2.0).unwrap();
+        let size = self.size();
+        two * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    pub fn corners(&self) -> [Vec3<T>; 8] {
+        let (a, b) = (self.min, self.max);
+        [Vec3::new(a.x, a.y, a.z), Vec3::new(b.x, a.y, a.z), Vec3::new(a.x, b.y, a.z), Vec3::new(b.x, b.y, a.z),
+         Vec3::new(a.x, a.y, b.z), Vec3::new(b.x, a.y, b.z), Vec3::new(a.x, b.y, b.z), Vec3::new(b.x, b.y, b.z)]
+    }
+
+    /* Points on the boundary are contained. */
+    pub fn contains_point(&self, p: &Vec3<T>) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x &&
+        p.y >= self.min.y && p.y <= self.max.y &&
+        p.z >= self.min.z && p.z <= self.max.z
+    }
+
+    pub fn contains(&self, other: &Aabb<T>) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    pub fn intersects(&self, other: &Aabb<T>) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x &&
+        self.min.y <= other.max.y && self.max.y >= other.min.y &&
+        self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    /* The box around the transformed corners, which contains everything the
+       original box did but is usually larger than the transformed contents. */
+    pub fn transform(&self, matrix: &Matrix44<T>) -> Aabb<T> {
+        let corners = self.corners();
+        Aabb::from_points(corners.iter().map(|corner| matrix.multVecMatrix(corner))).unwrap()
+    }
+}
+
+impl Aabb<f64> {
+    /* The box around the vertices of the object's triangles. Vertices no
+       triangle uses are left out. */
+    pub fn from_object(object: &Object) -> Option<Aabb<f64>> {
+        Aabb::from_points(triangle_points(object, |_| true))
+    }
+
+    /* The box around the triangles in the named group. */
+    pub fn from_group(object: &Object, group: &str) -> Option<Aabb<f64>> {
+        Aabb::from_points(triangle_points(object, |triangle| object.groups(triangle).iter().any(|g| g == group)))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingSphere<T: num::Float> {
+    pub center: Vec3<T>,
+    pub radius: T,
+}
+
+impl<T: num::Float> BoundingSphere<T> {
+    pub fn new(center: Vec3<T>, radius: T) -> BoundingSphere<T> {
+        BoundingSphere{center, radius}
+    }
+
+    /* Centered on the bounding box of the points, which is simple and stable
+       but can be up to sqrt(3) times larger than the smallest sphere. None
+       when there are no points. */
+    pub fn from_points<I: IntoIterator<Item = Vec3<T>>>(points: I) -> Option<BoundingSphere<T>> {
+        let points: Vec<Vec3<T>> = points.into_iter().collect();
+        let center = Aabb::from_points(points.iter().cloned())?.center();
+        let radius = points.iter().fold(num::cast::<f64, T>(0.0).unwrap(), |radius, p| radius.max(center.distance(*p)));
+
+        Some(BoundingSphere::new(center, radius))
+    }
+
+    pub fn union(&self, other: &BoundingSphere<T>) -> BoundingSphere<T> {
+        let two: T = num::cast::<f64, T>(2.0).unwrap();
+        let distance = self.center.distance(other.center);
+
+        if distance + other.radius <= self.radius {
+            *self
+        } else if distance + self.radius <= other.radius {
+            *other
+        } else {
+            /* The new sphere spans from the far side of one to the far side
+               of the other, along the line between the centers. */
+            let radius = (distance + self.radius + other.radius) / two;
+            let direction = (other.center - self.center) / distance;
+            BoundingSphere::new(self.center + direction * (radius - self.radius), radius)
+        }
+    }
+
+    pub fn contains_point(&self, p: &Vec3<T>) -> bool {
+        self.center.distance(*p) <= self.radius
+    }
+
+    pub fn contains(&self, other: &BoundingSphere<T>) -> bool {
+        self.center.distance(other.center) + other.radius <= self.radius
+    }
+
+    pub fn intersects(&self, other: &BoundingSphere<T>) -> bool {
+        self.center.distance(other.center) <= self.radius + other.radius
+    }
+
+    /* The radius grows by an upper bound on how far the upper 3x3 of the
+       matrix can stretch a vector: the square root of its largest row sum
+       times its largest column sum of absolute values. That is never less
+       than the spectral norm, so the sphere stays a bound under non-uniform
+       scaling and shear, and it is exact for scaling alone. */
+    pub fn transform(&self, matrix: &Matrix44<T>) -> BoundingSphere<T> {
+        let zero = num::cast::<f64, T>(0.0).unwrap();
+        let mut row_sum = zero;
+        let mut column_sum = zero;
+        for i in 0..3 {
+            row_sum = row_sum.max((0..3).fold(zero, |sum, j| sum + matrix[(i, j)].abs()));
+            column_sum = column_sum.max((0..3).fold(zero, |sum, j| sum + matrix[(j, i)].abs()));
+        }
+        let scale = (row_sum * column_sum).sqrt();
+
+        BoundingSphere::new(matrix.multVecMatrix(&self.center), self.radius * scale)
+    }
+}
+
+impl BoundingSphere<f64> {
+    pub fn from_object(object: &Object) -> Option<BoundingSphere<f64>> {
+        BoundingSphere::from_points(triangle_points(object, |_| true))
+    }
+
+    pub fn from_group(object: &Object, group: &str) -> Option<BoundingSphere<f64>> {
+        BoundingSphere::from_points(triangle_points(object, |triangle| object.groups(triangle).iter().any(|g| g == group)))
+    }
+}
+
+fn triangle_points<F: Fn(&Triangle) -> bool>(object: &Object, include: F) -> Vec<Vec3<f64>> {
+    let point = |v: &Vertex| Vec3::new(v.x, v.y, v.z);
+
+    object.triangles.iter()
+        .filter(|triangle| include(triangle))
+        .flat_map(|triangle| vec![point(&triangle.v1), point(&triangle.v2), point(&triangle.v3)])
+        .collect()
+}
+
+
+
+
+
+#[cfg(test)]
+fn two_groups() -> Object {
+    use wavefront::parse_object;
+
+    parse_object("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 2.0 0.0
+v 10.0 10.0 10.0
+v 12.0 10.0 10.0
+v 10.0 10.0 14.0
+v -50.0 -50.0 -50.0
+g near
+f 1 2 3
+g far
+f 4 5 6
+").unwrap()
+}
+
+#[test]
+fn aabb_can_be_created_from_points() {
+    let aabb: Aabb<f64> = Aabb::from_points(vec![Vec3::new(1.0, -2.0, 3.0), Vec3::new(-1.0, 5.0, 0.0), Vec3::new(0.0, 0.0, 4.0)]).unwrap();
+    assert_eq!(Vec3::new(-1.0, -2.0, 0.0), aabb.min);
+    assert_eq!(Vec3::new(1.0, 5.0, 4.0), aabb.max);
+    assert_eq!(Vec3::new(0.0, 1.5, 2.0), aabb.center());
+    assert_eq!(Vec3::new(2.0, 7.0, 4.0), aabb.size());
+    assert_delta!(aabb.surface_area(), 100.0, 0.0001);
+
+    assert_eq!(None, Aabb::<f64>::from_points(vec![]));
+}
+
+#[test]
+fn aabb_can_be_created_from_an_object_or_a_group() {
+    let object = two_groups();
+
+    /* The unused vertex at -50 is left out. */
+    let all = Aabb::from_object(&object).unwrap();
+    assert_eq!(Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(12.0, 10.0, 14.0)), all);
+
+    let near = Aabb::from_group(&object, "near").unwrap();
+    assert_eq!(Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 0.0)), near);
+
+    assert_eq!(None, Aabb::from_group(&object, "missing"));
+}
+
+#[test]
+fn aabb_can_union_contain_and_intersect() {
+    let a: Aabb<f64> = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 2.0));
+    let b: Aabb<f64> = Aabb::new(Vec3::new(1.0, 1.0, 1.0), Vec3::new(3.0, 3.0, 3.0));
+    let c: Aabb<f64> = Aabb::new(Vec3::new(5.0, 0.0, 0.0), Vec3::new(6.0, 1.0, 1.0));
+
+    let ab = a.union(&b);
+    assert_eq!(Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(3.0, 3.0, 3.0)), ab);
+    assert!(ab.contains(&a));
+    assert!(ab.contains(&b));
+    assert!(!a.contains(&b));
+
+    assert!(a.contains_point(&Vec3::new(2.0, 0.0, 1.0)));
+    assert!(!a.contains_point(&Vec3::new(2.1, 0.0, 1.0)));
+
+    assert!(a.intersects(&b));
+    assert!(!a.intersects(&c));
+}
+
+#[test]
+fn aabb_can_be_transformed() {
+    let a: Aabb<f64> = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+
+    let moved = a.transform(&Matrix44::new_translation(5.0, 0.0, 0.0));
+    assert_eq!(Aabb::new(Vec3::new(4.0, -1.0, -1.0), Vec3::new(6.0, 1.0, 1.0)), moved);
+
+    /* A 45 degree turn about z makes the box wider in x and y. */
+    let turned = a.transform(&Matrix44::new_rotation_z(45.0_f64.to_radians()));
+    assert_delta!(turned.max.x, 2.0_f64.sqrt(), 0.0001);
+    assert_delta!(turned.min.y, -(2.0_f64.sqrt()), 0.0001);
+    assert_delta!(turned.max.z, 1.0, 0.0001);
+}
+
+#[test]
+fn bounding_sphere_can_be_created_from_points_and_objects() {
+    let sphere: BoundingSphere<f64> = BoundingSphere::from_points(vec![Vec3::new(-2.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)]).unwrap();
+    assert_eq!(Vec3::new(0.0, 0.5, 0.0), sphere.center);
+    assert_delta!(sphere.radius, 4.25_f64.sqrt(), 0.0001);
+
+    let object = two_groups();
+    let far = BoundingSphere::from_group(&object, "far").unwrap();
+    assert_eq!(Vec3::new(11.0, 10.0, 12.0), far.center);
+    assert_delta!(far.radius, 5.0_f64.sqrt(), 0.0001);
+
+    let all = BoundingSphere::from_object(&object).unwrap();
+    for triangle in &object.triangles {
+        assert!(all.contains_point(&Vec3::new(triangle.v1.x, triangle.v1.y, triangle.v1.z)));
+    }
+
+    assert_eq!(None, BoundingSphere::<f64>::from_points(vec![]));
+}
+
+#[test]
+fn bounding_sphere_can_union_contain_and_intersect() {
+    let a: BoundingSphere<f64> = BoundingSphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0);
+    let b: BoundingSphere<f64> = BoundingSphere::new(Vec3::new(4.0, 0.0, 0.0), 1.0);
+    let inner: BoundingSphere<f64> = BoundingSphere::new(Vec3::new(0.5, 0.0, 0.0), 0.25);
+
+    let ab = a.union(&b);
+    assert_eq!(Vec3::new(2.0, 0.0, 0.0), ab.center);
+    assert_delta!(ab.radius, 3.0, 0.0001);
+    assert!(ab.contains(&a));
+    assert!(ab.contains(&b));
+
+    assert_eq!(a, a.union(&inner));
+    assert_eq!(a, inner.union(&a));
+
+    assert!(!a.intersects(&b));
+    assert!(a.intersects(&inner));
+    assert!(a.contains_point(&Vec3::new(0.0, 1.0, 0.0)));
+    assert!(!a.contains_point(&Vec3::new(0.0, 1.1, 0.0)));
+}
+
+#[test]
+fn bounding_sphere_can_be_transformed() {
+    let a: BoundingSphere<f64> = BoundingSphere::new(Vec3::new(1.0, 0.0, 0.0), 1.0);
+    let transformed = a.transform(&(Matrix44::new_scaling(1.0, 3.0, 2.0) * Matrix44::new_translation(0.0, 0.0, 5.0)));
+    assert_eq!(Vec3::new(1.0, 0.0, 5.0), transformed.center);
+    assert_delta!(transformed.radius, 3.0, 0.0001);
+}
+
+#[test]
+fn bounding_sphere_should_stay_a_bound_under_shear() {
+    let a: BoundingSphere<f64> = BoundingSphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0);
+    let shear: Matrix44<f64> = Matrix44::new(1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+    let transformed = a.transform(&shear);
+
+    /* The direction stretched the most, by the golden ratio. */
+    let golden = (1.0 + 5.0_f64.sqrt()) / 2.0;
+    let stretched = Vec3::new(1.0, golden, 0.0) * (1.0 / (golden * golden + 1.0).sqrt());
+    assert!(transformed.contains_point(&shear.multVecMatrix(&stretched)));
+    assert!(transformed.radius >= golden);
+}
@@ -0,0 +1,564 @@
+use geometry::bounds::*;
+use geometry::intersection::*;
+use geometry::vector::*;
+use wavefront::object_parser::{self, Object};
+
+/* Leaves with this many triangles or fewer are never split. */
+const MAX_LEAF_SIZE: usize = 4;
+/* Candidate split planes are placed between this many bins of centroids. */
+const SAH_BINS: usize = 16;
+/* Cost of visiting a node, relative to intersecting a triangle. */
+const TRAVERSAL_COST: f64 = 1.0;
+
+/* A bounding volume hierarchy over a list of triangles, for ray and overlap
+   queries that only look at the triangles near the query. Triangles are
+   referred to by their index in the list the hierarchy was built from, so
+   for an object by their index in Object::triangles. */
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    triangles: Vec<Triangle<f64>>,
+    /* Triangle indices, ordered so every leaf owns a contiguous range. */
+    indices: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf{aabb: Aabb<f64>, start: usize, count: usize},
+    Inner{aabb: Aabb<f64>, left: usize, right: usize},
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BvhHit {
+    pub triangle: usize,
+    pub hit: TriangleHit<f64>,
+}
+
+impl Node {
+    fn aabb(&self) -> &Aabb<f64> {
+        match *self {
+            Node::Leaf{ref aabb, ..} | Node::Inner{ref aabb, ..} => aabb,
+        }
+    }
+}
+
+impl Bvh {
+    pub fn new(triangles: Vec<Triangle<f64>>) -> Bvh {
+        let mut bvh = Bvh{nodes: vec![], indices: (0..triangles.len()).collect(), triangles};
+        if !bvh.triangles.is_empty() {
+            let bounds: Vec<Aabb<f64>> = bvh.triangles.iter().map(|t| t.aabb()).collect();
+            let centroids: Vec<Vec3<f64>> = bvh.triangles.iter().map(|t| t.centroid()).collect();
+            let count = bvh.triangles.len();
+            bvh.build(&bounds, &centroids, 0, count);
+        }
+
+        bvh
+    }
+
+    pub fn from_object(object: &Object) -> Bvh {
+        Bvh::new(object.triangles.iter().map(Triangle::from).collect())
+    }
+
+    pub fn from_triangles(triangles: &[object_parser::Triangle]) -> Bvh {
+        Bvh::new(triangles.iter().map(Triangle::from).collect())
+    }
+
+    pub fn triangle(&self, index: usize) -> &Triangle<f64> {
+        &self.triangles[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /* The bounds of every triangle, or None when there are none. */
+    pub fn aabb(&self) -> Option<&Aabb<f64>> {
+        self.nodes.first().map(|node| node.aabb())
+    }
+
+    /* Builds the node for indices[start..start + count] and returns its
+       position in nodes. */
+    fn build(&mut self, bounds: &[Aabb<f64>], centroids: &[Vec3<f64>], start: usize, count: usize) -> usize {
+        let range = &self.indices[start..start + count];
+        let mut aabb = bounds[range[0]];
+        let mut centroid_bounds = Aabb::new(centroids[range[0]], centroids[range[0]]);
+        for &i in &range[1..] {
+            aabb = aabb.union(&bounds[i]);
+            centroid_bounds.extend(centroids[i]);
+        }
+
+        let node = self.nodes.len();
+        self.nodes.push(Node::Leaf{aabb, start, count});
+        if count <= MAX_LEAF_SIZE {
+            return node;
+        }
+
+        let split = match find_split(bounds, centroids, &self.indices[start..start + count], &aabb, &centroid_bounds) {
+            Some(split) => split,
+            None => return node,
+        };
+        let (axis, bin) = split;
+
+        /* Partition the range so triangles in bins below the split come first. */
+        let mut middle = start;
+        for j in start..start + count {
+            if centroid_bin(&centroids[self.indices[j]], &centroid_bounds, axis) < bin {
+                self.indices.swap(middle, j);
+                middle += 1;
+            }
+        }
+
+        let left = self.build(bounds, centroids, start, middle - start);
+        let right = self.build(bounds, centroids, middle, start + count - middle);
+        self.nodes[node] = Node::Inner{aabb, left, right};
+
+        node
+    }
+
+    /* Every triangle the ray hits, nearest first. */
+    pub fn intersect(&self, ray: &Ray<f64>) -> Vec<BvhHit> {
+        let mut hits = vec![];
+        self.visit_ray(ray, f64::INFINITY, |hit| {
+            hits.push(hit);
+            false
+        });
+        hits.sort_by(|a, b| a.hit.t.total_cmp(&b.hit.t));
+
+        hits
+    }
+
+    pub fn nearest_hit(&self, ray: &Ray<f64>) -> Option<BvhHit> {
+        let mut stack = vec![];
+        let mut nearest: Option<BvhHit> = None;
+        if let Some(root) = self.nodes.first() {
+            if ray.intersect_aabb(root.aabb()).is_some() {
+                stack.push(0);
+            }
+        }
+
+        while let Some(node) = stack.pop() {
+            match self.nodes[node] {
+                Node::Leaf{ref aabb, start, count} => {
+                    let nearest_t = nearest.map_or(f64::INFINITY, |n| n.hit.t);
+                    if ray.intersect_aabb(aabb).is_none_or(|(t_min, _)| t_min > nearest_t) {
+                        continue;
+                    }
+                    for &i in &self.indices[start..start + count] {
+                        if let Some(hit) = self.intersect_triangle(ray, i) {
+                            if nearest.is_none_or(|n| hit.t < n.hit.t) {
+                                nearest = Some(BvhHit{triangle: i, hit});
+                            }
+                        }
+                    }
+                },
+                Node::Inner{left, right, ..} => {
+                    /* Visit the nearer child first, so the further one can
+                       often be skipped. */
+                    let nearest_t = nearest.map_or(f64::INFINITY, |n| n.hit.t);
+                    let entry = |child: usize| ray.intersect_aabb(self.nodes[child].aabb()).map(|(t_min, _)| t_min).filter(|&t| t <= nearest_t);
+                    match (entry(left), entry(right)) {
+                        (Some(l), Some(r)) => {
+                            if l <= r {
+                                stack.push(right);
+                                stack.push(left);
+                            } else {
+                                stack.push(left);
+                                stack.push(right);
+                            }
+                        },
+                        (Some(_), None) => stack.push(left),
+                        (None, Some(_)) => stack.push(right),
+                        (None, None) => (),
+                    }
+                },
+            }
+        }
+
+        nearest
+    }
+
+    /* Whether the ray hits anything closer than max_t, which is all a
+       visibility test needs. Stops at the first hit found. */
+    pub fn any_hit(&self, ray: &Ray<f64>, max_t: f64) -> bool {
+        let mut found = false;
+        self.visit_ray(ray, max_t, |_| {
+            found = true;
+            true
+        });
+
+        found
+    }
+
+    /* Triangles with infinite vertices can be hit at a distance that is
+       not a number, which would never compare as nearer or farther than
+       anything else. */
+    fn intersect_triangle(&self, ray: &Ray<f64>, i: usize) -> Option<TriangleHit<f64>> {
+        ray.intersect_triangle(&self.triangles[i]).filter(|hit| !hit.t.is_nan())
+    }
+
+    /* Calls visit with every hit closer than max_t, in no particular order,
+       until it returns true. */
+    fn visit_ray<F: FnMut(BvhHit) -> bool>(&self, ray: &Ray<f64>, max_t: f64, mut visit: F) {
+        let mut stack = vec![];
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if ray.intersect_aabb(node.aabb()).is_none_or(|(t_min, _)| t_min > max_t) {
+                continue;
+            }
+
+            match *node {
+                Node::Leaf{start, count, ..} => {
+                    for &i in &self.indices[start..start + count] {
+                        if let Some(hit) = self.intersect_triangle(ray, i) {
+                            if hit.t < max_t && visit(BvhHit{triangle: i, hit}) {
+                                return;
+                            }
+                        }
+                    }
+                },
+                Node::Inner{left, right, ..} => {
+                    stack.push(right);
+                    stack.push(left);
+                },
+            }
+        }
+    }
+
+    /* The triangles whose bounding boxes overlap the box, in no particular
+       order. */
+    pub fn overlapping(&self, aabb: &Aabb<f64>) -> Vec<usize> {
+        self.collect(|node_aabb| aabb.intersects(node_aabb))
+    }
+
+    /* The triangles whose bounding boxes are at least partly inside the
+       frustum, in no particular order. Like Frustum::intersects_aabb this is
+       conservative, so triangles just outside the corners can be included. */
+    pub fn in_frustum(&self, frustum: &Frustum<f64>) -> Vec<usize> {
+        self.collect(|node_aabb| frustum.intersects_aabb(node_aabb))
+    }
+
+    fn collect<F: Fn(&Aabb<f64>) -> bool>(&self, overlaps: F) -> Vec<usize> {
+        let mut found = vec![];
+        let mut stack = vec![];
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !overlaps(node.aabb()) {
+                continue;
+            }
+
+            match *node {
+                Node::Leaf{start, count, ..} => {
+                    for &i in &self.indices[start..start + count] {
+                        if overlaps(&self.triangles[i].aabb()) {
+                            found.push(i);
+                        }
+                    }
+                },
+                Node::Inner{left, right, ..} => {
+                    stack.push(right);
+                    stack.push(left);
+                },
+            }
+        }
+
+        found
+    }
+}
+
+/* The surface area heuristic: the expected cost of a split is the cost
+   of each side weighted by the chance a ray through the parent box also
+   goes through the side's box, which is proportional to its surface
+   area. Returns the axis and first bin of the right side of the best
+   split, or None when no split is cheaper than a leaf. */
+fn find_split(bounds: &[Aabb<f64>], centroids: &[Vec3<f64>], indices: &[usize], aabb: &Aabb<f64>, centroid_bounds: &Aabb<f64>) -> Option<(usize, usize)> {
+    let mut best: Option<(f64, usize, usize)> = None;
+
+    for axis in 0..3 {
+        if axis_of(&centroid_bounds.size(), axis) <= 0.0 {
+            continue;
+        }
+
+        let mut bin_bounds: Vec<Option<Aabb<f64>>> = vec![None; SAH_BINS];
+        let mut bin_counts = [0usize; SAH_BINS];
+        for &i in indices {
+            let bin = centroid_bin(&centroids[i], centroid_bounds, axis);
+            bin_counts[bin] += 1;
+            bin_bounds[bin] = Some(match bin_bounds[bin] {
+                Some(b) => b.union(&bounds[i]),
+                None => bounds[i],
+            });
+        }
+
+        /* Sweep from the right to get the area and count of every right
+           side, then from the left to price each split. */
+        let mut right_areas = [0.0; SAH_BINS];
+        let mut right_counts = [0usize; SAH_BINS];
+        let mut accumulated: Option<Aabb<f64>> = None;
+        let mut accumulated_count = 0;
+        for bin in (1..SAH_BINS).rev() {
+            accumulated = union_option(accumulated, bin_bounds[bin]);
+            accumulated_count += bin_counts[bin];
+            right_areas[bin] = accumulated.map_or(0.0, |b| b.surface_area());
+            right_counts[bin] = accumulated_count;
+        }
+
+        let mut accumulated: Option<Aabb<f64>> = None;
+        let mut accumulated_count = 0;
+        for bin in 1..SAH_BINS {
+            accumulated = union_option(accumulated, bin_bounds[bin - 1]);
+            accumulated_count += bin_counts[bin - 1];
+            if accumulated_count == 0 || right_counts[bin] == 0 {
+                continue;
+            }
+
+            let left_area = accumulated.map_or(0.0, |b| b.surface_area());
+            let cost = left_area * accumulated_count as f64 + right_areas[bin] * right_counts[bin] as f64;
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, bin));
+            }
+        }
+    }
+
+    let (cost, axis, bin) = best?;
+    let area = aabb.surface_area();
+    let split_cost = if area > 0.0 { TRAVERSAL_COST + cost / area } else { TRAVERSAL_COST + indices.len() as f64 };
+
+    if split_cost < indices.len() as f64 {
+        Some((axis, bin))
+    } else {
+        None
+    }
+}
+
+fn axis_of(v: &Vec3<f64>, axis: usize) -> f64 {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+fn centroid_bin(centroid: &Vec3<f64>, centroid_bounds: &Aabb<f64>, axis: usize) -> usize {
+    let min = axis_of(&centroid_bounds.min, axis);
+    let extent = axis_of(&centroid_bounds.size(), axis);
+    let bin = ((axis_of(centroid, axis) - min) / extent * SAH_BINS as f64) as usize;
+
+    bin.min(SAH_BINS - 1)
+}
+
+fn union_option(a: Option<Aabb<f64>>, b: Option<Aabb<f64>>) -> Option<Aabb<f64>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(&b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+
+
+
+
+/* A stack of layers of small tilted triangles, enough for several levels. */
+#[cfg(test)]
+fn layered_triangles() -> Vec<Triangle<f64>> {
+    let mut triangles = vec![];
+    for layer in 0..4 {
+        for i in 0..12 {
+            for j in 0..12 {
+                let (x, y, z) = (i as f64, j as f64, -(layer as f64) * 3.0 - 0.1 * (i as f64));
+                triangles.push(Triangle::new(Vec3::new(x, y, z), Vec3::new(x + 0.9, y, z), Vec3::new(x, y + 0.9, z - 0.5)));
+            }
+        }
+    }
+
+    triangles
+}
+
+#[cfg(test)]
+fn brute_force_nearest(triangles: &[Triangle<f64>], ray: &Ray<f64>) -> Option<(usize, f64)> {
+    let mut nearest: Option<(usize, f64)> = None;
+    for (i, triangle) in triangles.iter().enumerate() {
+        if let Some(hit) = ray.intersect_triangle(triangle) {
+            if nearest.is_none_or(|(_, t)| hit.t < t) {
+                nearest = Some((i, hit.t));
+            }
+        }
+    }
+
+    nearest
+}
+
+#[cfg(test)]
+fn test_rays() -> Vec<Ray<f64>> {
+    let mut rays = vec![];
+    for i in 0..20 {
+        for j in 0..20 {
+            let (x, y) = (i as f64 * 0.67 - 0.5, j as f64 * 0.61 - 0.3);
+            rays.push(Ray::new(Vec3::new(x, y, 5.0), Vec3::new(0.05 * (j as f64 - 10.0), 0.03 * (i as f64 - 10.0), -1.0)));
+        }
+    }
+    rays.push(Ray::new(Vec3::new(20.0, 20.0, 20.0), Vec3::new(1.0, 0.0, 0.0)));
+
+    rays
+}
+
+#[test]
+fn bvh_should_split_large_meshes() {
+    let bvh = Bvh::new(layered_triangles());
+    assert_eq!(576, bvh.len());
+    assert!(bvh.nodes.len() > 1);
+
+    /* Every triangle is in exactly one leaf, and small leaves. */
+    let mut seen = vec![false; bvh.len()];
+    for node in &bvh.nodes {
+        if let Node::Leaf{start, count, ..} = *node {
+            assert!(count <= 16);
+            for &i in &bvh.indices[start..start + count] {
+                assert!(!seen[i]);
+                seen[i] = true;
+            }
+        }
+    }
+    assert!(seen.iter().all(|&s| s));
+}
+
+#[test]
+fn bvh_nearest_hit_should_match_brute_force() {
+    let triangles = layered_triangles();
+    let bvh = Bvh::new(triangles.clone());
+    let mut hits = 0;
+
+    for ray in test_rays() {
+        let expected = brute_force_nearest(&triangles, &ray);
+        let actual = bvh.nearest_hit(&ray);
+        match (expected, actual) {
+            (Some((_, t)), Some(hit)) => {
+                assert_delta!(hit.hit.t, t, 0.000001);
+                hits += 1;
+            },
+            (None, None) => (),
+            _ => panic!("expected {:?}, got {:?}", expected, actual),
+        }
+    }
+    assert!(hits > 100);
+}
+
+#[test]
+fn bvh_intersect_should_return_every_hit_in_order() {
+    let triangles = layered_triangles();
+    let bvh = Bvh::new(triangles.clone());
+
+    for ray in test_rays() {
+        let hits = bvh.intersect(&ray);
+        let expected = triangles.iter().filter(|t| ray.intersect_triangle(t).is_some()).count();
+        assert_eq!(expected, hits.len());
+        for pair in hits.windows(2) {
+            assert!(pair[0].hit.t <= pair[1].hit.t);
+        }
+        if let Some(first) = hits.first() {
+            assert_eq!(first.hit.t, bvh.nearest_hit(&ray).unwrap().hit.t);
+        }
+    }
+}
+
+#[test]
+fn bvh_any_hit_should_respect_the_distance() {
+    let bvh = Bvh::new(layered_triangles());
+    let ray = Ray::new(Vec3::new(0.2, 0.2, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let nearest = bvh.nearest_hit(&ray).unwrap().hit.t;
+
+    assert!(bvh.any_hit(&ray, nearest + 0.01));
+    assert!(!bvh.any_hit(&ray, nearest - 0.01));
+    assert!(!bvh.any_hit(&Ray::new(Vec3::new(0.2, 0.2, 5.0), Vec3::new(0.0, 0.0, 1.0)), 1000.0));
+}
+
+#[test]
+fn bvh_overlapping_should_match_brute_force() {
+    let triangles = layered_triangles();
+    let bvh = Bvh::new(triangles.clone());
+    let query = Aabb::new(Vec3::new(2.5, 3.5, -4.0), Vec3::new(5.0, 4.0, -2.0));
+
+    let mut found = bvh.overlapping(&query);
+    found.sort();
+    let expected: Vec<usize> = (0..triangles.len()).filter(|&i| query.intersects(&triangles[i].aabb())).collect();
+    assert!(!expected.is_empty());
+    assert_eq!(expected, found);
+}
+
+#[test]
+fn bvh_in_frustum_should_match_brute_force() {
+    use geometry::matrix::Matrix44;
+
+    let triangles = layered_triangles();
+    let bvh = Bvh::new(triangles.clone());
+    let world_to_camera = Matrix44::new_translation(-3.0, -3.0, -10.0);
+    let frustum = Frustum::from_matrix(&(world_to_camera * Matrix44::new_frustum(-0.2, 0.2, -0.2, 0.2, 1.0, 100.0))).unwrap();
+
+    let mut found = bvh.in_frustum(&frustum);
+    found.sort();
+    let expected: Vec<usize> = (0..triangles.len()).filter(|&i| frustum.intersects_aabb(&triangles[i].aabb())).collect();
+    assert!(!expected.is_empty());
+    assert!(expected.len() < triangles.len());
+    assert_eq!(expected, found);
+}
+
+#[test]
+fn bvh_can_be_built_from_an_object() {
+    use wavefront::parse_object;
+
+    let object = parse_object("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 0.0 0.0 -1.0
+v 1.0 0.0 -1.0
+v 0.0 1.0 -1.0
+f 1 2 3
+f 4 5 6
+").unwrap();
+    let bvh = Bvh::from_object(&object);
+    assert_eq!(2, bvh.len());
+    assert_eq!(Some(&Aabb::new(Vec3::new(0.0, 0.0, -1.0), Vec3::new(1.0, 1.0, 0.0))), bvh.aabb());
+
+    let hit = bvh.nearest_hit(&Ray::new(Vec3::new(0.2, 0.2, -5.0), Vec3::new(0.0, 0.0, 1.0))).unwrap();
+    assert_eq!(1, hit.triangle);
+    assert_delta!(hit.hit.t, 4.0, 0.0001);
+}
+
+#[test]
+fn bvh_can_be_empty() {
+    let bvh = Bvh::new(vec![]);
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(bvh.is_empty());
+    assert_eq!(None, bvh.aabb());
+    assert_eq!(None, bvh.nearest_hit(&ray));
+    assert!(!bvh.any_hit(&ray, 100.0));
+    assert!(bvh.intersect(&ray).is_empty());
+    assert!(bvh.overlapping(&Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0))).is_empty());
+}
+
+#[test]
+fn bvh_should_skip_hits_that_are_not_a_number() {
+    let triangles = vec![
+        Triangle::new(Vec3::new(f64::INFINITY, 0.0, -1.0), Vec3::new(-1.0, -1.0, -1.0), Vec3::new(-1.0, 1.0, -1.0)),
+        Triangle::new(Vec3::new(-1.0, -1.0, 0.0), Vec3::new(1.0, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+        Triangle::new(Vec3::new(-1.0, -1.0, -2.0), Vec3::new(1.0, -1.0, -2.0), Vec3::new(0.0, 1.0, -2.0)),
+    ];
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(ray.intersect_triangle(&triangles[0]).unwrap().t.is_nan());
+
+    let bvh = Bvh::new(triangles);
+    assert_eq!(vec![5.0, 7.0], bvh.intersect(&ray).iter().map(|h| h.hit.t).collect::<Vec<_>>());
+    assert_eq!(5.0, bvh.nearest_hit(&ray).unwrap().hit.t);
+}
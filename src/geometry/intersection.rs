@@ -0,0 +1,417 @@
+extern crate num;
+
+use geometry::bounds::*;
+use geometry::matrix::*;
+use geometry::vector::*;
+use wavefront::object_parser;
+
+/* A half line from origin along direction. The direction does not need to
+   be normalized; distances along the ray are in multiples of it. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ray<T: num::Float> {
+    pub origin: Vec3<T>,
+    pub direction: Vec3<T>,
+}
+
+/* Where a ray hits a triangle: the point is origin + t * direction, or
+   (1 - u - v) * a + u * b + v * c on the triangle. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TriangleHit<T: num::Float> {
+    pub t: T,
+    pub u: T,
+    pub v: T,
+}
+
+/* The points p with normal . p + d = 0. The normal is kept normalized, so
+   distances are in world units. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Plane<T: num::Float> {
+    pub normal: Vec3<T>,
+    pub d: T,
+}
+
+/* A triangle as three points, without the materials and texture
+   coordinates of a wavefront triangle. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Triangle<T: num::Float> {
+    pub a: Vec3<T>,
+    pub b: Vec3<T>,
+    pub c: Vec3<T>,
+}
+
+/* The six planes around a view volume, with normals pointing inwards:
+   left, right, bottom, top, near and far. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Frustum<T: num::Float> {
+    pub planes: [Plane<T>; 6],
+}
+
+impl<T: num::Float> Ray<T> {
+    pub fn new(origin: Vec3<T>, direction: Vec3<T>) -> Ray<T> {
+        Ray{origin, direction}
+    }
+
+    pub fn at(&self, t: T) -> Vec3<T> {
+        self.origin + self.direction * t
+    }
+
+    /* Möller–Trumbore. Both sides of the triangle are hit; hits behind the
+       origin and rays parallel to the triangle are not. */
+    pub fn intersect_triangle(&self, triangle: &Triangle<T>) -> Option<TriangleHit<T>> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+
+        let edge1 = triangle.b - triangle.a;
+        let edge2 = triangle.c - triangle.a;
+        let pvec = self.direction.cross_product(edge2);
+        let det = edge1.dot_product(pvec);
+
+        /* The determinant scales with the edges and direction, so compare it
+           against them rather than against a fixed epsilon. */
+        let epsilon = T::epsilon() * edge1.length() * edge2.length() * self.direction.length();
+        if det.abs() <= epsilon {
+            return None;
+        }
+        let inverse_det = one / det;
+
+        let tvec = self.origin - triangle.a;
+        let u = tvec.dot_product(pvec) * inverse_det;
+        if u < zero || u > one {
+            return None;
+        }
+
+        let qvec = tvec.cross_product(edge1);
+        let v = self.direction.dot_product(qvec) * inverse_det;
+        if v < zero || u + v > one {
+            return None;
+        }
+
+        let t = edge2.dot_product(qvec) * inverse_det;
+        if t < zero {
+            return None;
+        }
+
+        Some(TriangleHit{t, u, v})
+    }
+
+    /* Slab test. Returns the range of t where the ray is inside the box,
+       clamped to start at the origin, or None when the ray misses it. */
+    pub fn intersect_aabb(&self, aabb: &Aabb<T>) -> Option<(T, T)> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+        let mut t_min = zero;
+        let mut t_max = T::infinity();
+
+        let axes = [(self.origin.x, self.direction.x, aabb.min.x, aabb.max.x),
+                    (self.origin.y, self.direction.y, aabb.min.y, aabb.max.y),
+                    (self.origin.z, self.direction.z, aabb.min.z, aabb.max.z)];
+        for &(origin, direction, min, max) in axes.iter() {
+            if direction == zero {
+                /* Parallel to this slab: inside it or never. */
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let inverse = one / direction;
+            let t1 = (min - origin) * inverse;
+            let t2 = (max - origin) * inverse;
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some((t_min, t_max))
+    }
+
+    /* The t where the ray crosses the plane, if it does in front of the
+       origin. */
+    pub fn intersect_plane(&self, plane: &Plane<T>) -> Option<T> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let denominator = plane.normal.dot_product(self.direction);
+        if denominator == zero {
+            return None;
+        }
+
+        let t = -plane.distance(&self.origin) / denominator;
+        if t >= zero { Some(t) } else { None }
+    }
+}
+
+impl<T: num::Float> Plane<T> {
+    /* None when the normal is zero. */
+    pub fn new(normal: Vec3<T>, d: T) -> Option<Plane<T>> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let length = normal.length();
+        if length == zero || !length.is_finite() {
+            return None;
+        }
+
+        Some(Plane{normal: normal / length, d: d / length})
+    }
+
+    pub fn from_point_normal(point: &Vec3<T>, normal: &Vec3<T>) -> Option<Plane<T>> {
+        Plane::new(*normal, -normal.dot_product(*point))
+    }
+
+    /* The plane through three points, facing the side they wind
+       counter-clockwise around. None when they are on one line. */
+    pub fn from_points(a: &Vec3<T>, b: &Vec3<T>, c: &Vec3<T>) -> Option<Plane<T>> {
+        Plane::from_point_normal(a, &(*b - *a).cross_product(*c - *a))
+    }
+
+    /* Positive on the side the normal points to. */
+    pub fn distance(&self, p: &Vec3<T>) -> T {
+        self.normal.dot_product(*p) + self.d
+    }
+
+    pub fn project(&self, p: &Vec3<T>) -> Vec3<T> {
+        *p - self.normal * self.distance(p)
+    }
+
+    /* Where the segment from a to b crosses the plane. Segments lying in the
+       plane or not reaching it have no single crossing. */
+    pub fn intersect_segment(&self, a: &Vec3<T>, b: &Vec3<T>) -> Option<Vec3<T>> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let distance_a = self.distance(a);
+        let distance_b = self.distance(b);
+
+        if (distance_a > zero && distance_b > zero) || (distance_a < zero && distance_b < zero) || distance_a == distance_b {
+            return None;
+        }
+
+        Some(a.lerp(*b, distance_a / (distance_a - distance_b)))
+    }
+}
+
+impl<T: num::Float> Frustum<T> {
+    /* Extracts the planes from a world to clip space matrix, such as
+       world_to_camera * projection (Gribb and Hartmann). With row vectors
+       clip space x is p . column 0, so the left plane -w <= x is
+       column 3 + column 0, and so on. None for degenerate matrices. */
+    pub fn from_matrix(matrix: &Matrix44<T>) -> Option<Frustum<T>> {
+        let column = |j: usize| {
+            let c = matrix.column(j);
+            (Vec3::new(c[0], c[1], c[2]), c[3])
+        };
+        let (w, w_d) = column(3);
+        let plane = |j: usize, sign: T| {
+            let (c, c_d) = column(j);
+            Plane::new(w + c * sign, w_d + c_d * sign)
+        };
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+
+        Some(Frustum{planes: [plane(0, one)?, plane(0, -one)?, plane(1, one)?, plane(1, -one)?, plane(2, one)?, plane(2, -one)?]})
+    }
+
+    pub fn contains_point(&self, p: &Vec3<T>) -> bool {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        self.planes.iter().all(|plane| plane.distance(p) >= zero)
+    }
+
+    /* Conservative: false only when the box is entirely outside one of the
+       planes, so some boxes near the corners pass without overlapping. */
+    pub fn intersects_aabb(&self, aabb: &Aabb<T>) -> bool {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        self.planes.iter().all(|plane| {
+            /* The corner furthest along the normal. */
+            let corner = Vec3::new(if plane.normal.x >= zero { aabb.max.x } else { aabb.min.x },
+                                   if plane.normal.y >= zero { aabb.max.y } else { aabb.min.y },
+                                   if plane.normal.z >= zero { aabb.max.z } else { aabb.min.z });
+            plane.distance(&corner) >= zero
+        })
+    }
+}
+
+impl<T: num::Float> Triangle<T> {
+    pub fn new(a: Vec3<T>, b: Vec3<T>, c: Vec3<T>) -> Triangle<T> {
+        Triangle{a, b, c}
+    }
+
+    /* Facing the side the points wind counter-clockwise around, and zero
+       for degenerate triangles. */
+    pub fn normal(&self) -> Vec3<T> {
+        (self.b - self.a).cross_product(self.c - self.a).normalize()
+    }
+
+    pub fn area(&self) -> T {
+        let half: T = num::cast::<f64, T>(0.5).unwrap();
+        (self.b - self.a).cross_product(self.c - self.a).length() * half
+    }
+
+    pub fn centroid(&self) -> Vec3<T> {
+        let three: T = num::cast::<f64, T>(3.0).unwrap();
+        (self.a + self.b + self.c) / three
+    }
+
+    pub fn point_at(&self, u: T, v: T) -> Vec3<T> {
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+        self.a * (one - u - v) + self.b * u + self.c * v
+    }
+
+    pub fn plane(&self) -> Option<Plane<T>> {
+        Plane::from_points(&self.a, &self.b, &self.c)
+    }
+
+    pub fn aabb(&self) -> Aabb<T> {
+        Aabb::new(self.a.min(self.b).min(self.c), self.a.max(self.b).max(self.c))
+    }
+}
+
+impl<'a> From<&'a object_parser::Triangle> for Triangle<f64> {
+    fn from(triangle: &'a object_parser::Triangle) -> Triangle<f64> {
+        let point = |v: &object_parser::Vertex| Vec3::new(v.x, v.y, v.z);
+        Triangle::new(point(&triangle.v1), point(&triangle.v2), point(&triangle.v3))
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+fn unit_triangle() -> Triangle<f64> {
+    Triangle::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0))
+}
+
+#[test]
+fn ray_can_hit_a_triangle() {
+    let ray: Ray<f64> = Ray::new(Vec3::new(0.25, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+    let hit = ray.intersect_triangle(&unit_triangle()).unwrap();
+    assert_delta!(hit.t, 2.0, 0.0001);
+    assert_delta!(hit.u, 0.25, 0.0001);
+    assert_delta!(hit.v, 0.5, 0.0001);
+    assert_eq!(ray.at(hit.t), unit_triangle().point_at(hit.u, hit.v));
+
+    /* From behind, too. */
+    let from_behind: Ray<f64> = Ray::new(Vec3::new(0.25, 0.5, -2.0), Vec3::new(0.0, 0.0, 4.0));
+    assert_delta!(from_behind.intersect_triangle(&unit_triangle()).unwrap().t, 0.5, 0.0001);
+}
+
+#[test]
+fn ray_can_miss_a_triangle() {
+    /* Outside the edges. */
+    let outside: Ray<f64> = Ray::new(Vec3::new(0.75, 0.75, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    assert_eq!(None, outside.intersect_triangle(&unit_triangle()));
+
+    /* Pointing away. */
+    let away: Ray<f64> = Ray::new(Vec3::new(0.25, 0.25, 1.0), Vec3::new(0.0, 0.0, 1.0));
+    assert_eq!(None, away.intersect_triangle(&unit_triangle()));
+
+    /* Parallel, in the triangle's plane. */
+    let parallel: Ray<f64> = Ray::new(Vec3::new(-1.0, 0.25, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(None, parallel.intersect_triangle(&unit_triangle()));
+}
+
+#[test]
+fn ray_can_hit_and_miss_an_aabb() {
+    let aabb: Aabb<f64> = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+
+    let through: Ray<f64> = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(Some((4.0, 6.0)), through.intersect_aabb(&aabb));
+
+    let inside: Ray<f64> = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+    assert_eq!(Some((0.0, 0.5)), inside.intersect_aabb(&aabb));
+
+    let diagonal: Ray<f64> = Ray::new(Vec3::new(-3.0, -3.0, -3.0), Vec3::new(1.0, 1.0, 1.0));
+    assert_eq!(Some((2.0, 4.0)), diagonal.intersect_aabb(&aabb));
+
+    let beside: Ray<f64> = Ray::new(Vec3::new(-5.0, 2.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(None, beside.intersect_aabb(&aabb));
+
+    let behind: Ray<f64> = Ray::new(Vec3::new(5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(None, behind.intersect_aabb(&aabb));
+
+    let skewed: Ray<f64> = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+    assert_eq!(None, skewed.intersect_aabb(&aabb));
+}
+
+#[test]
+fn ray_can_hit_a_plane() {
+    let plane: Plane<f64> = Plane::new(Vec3::new(0.0, 2.0, 0.0), -4.0).unwrap();
+    let ray: Ray<f64> = Ray::new(Vec3::new(1.0, 0.0, 1.0), Vec3::new(0.0, 4.0, 0.0));
+    assert_eq!(Some(0.5), ray.intersect_plane(&plane));
+
+    let away: Ray<f64> = Ray::new(Vec3::new(1.0, 0.0, 1.0), Vec3::new(0.0, -1.0, 0.0));
+    assert_eq!(None, away.intersect_plane(&plane));
+
+    let parallel: Ray<f64> = Ray::new(Vec3::new(1.0, 0.0, 1.0), Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(None, parallel.intersect_plane(&plane));
+}
+
+#[test]
+fn plane_measures_signed_distance() {
+    let plane: Plane<f64> = Plane::from_point_normal(&Vec3::new(0.0, 0.0, 3.0), &Vec3::new(0.0, 0.0, 5.0)).unwrap();
+    assert_delta!(plane.distance(&Vec3::new(7.0, -2.0, 5.0)), 2.0, 0.0001);
+    assert_delta!(plane.distance(&Vec3::new(7.0, -2.0, 1.0)), -2.0, 0.0001);
+    assert_eq!(Vec3::new(7.0, -2.0, 3.0), plane.project(&Vec3::new(7.0, -2.0, 1.0)));
+
+    /* Counter-clockwise points face +z. */
+    let from_points = unit_triangle().plane().unwrap();
+    assert_eq!(Vec3::new(0.0, 0.0, 1.0), from_points.normal);
+    assert_delta!(from_points.d, 0.0, 0.0001);
+
+    assert_eq!(None, Plane::new(Vec3::new(0.0, 0.0, 0.0), 1.0));
+    assert_eq!(None, Plane::from_points(&Vec3::new(0.0, 0.0, 0.0), &Vec3::new(1.0, 1.0, 1.0), &Vec3::new(2.0, 2.0, 2.0)));
+}
+
+#[test]
+fn plane_can_intersect_a_segment() {
+    let plane: Plane<f64> = Plane::new(Vec3::new(1.0, 0.0, 0.0), -1.0).unwrap();
+    assert_eq!(Some(Vec3::new(1.0, 0.5, 0.0)), plane.intersect_segment(&Vec3::new(0.0, 0.0, 0.0), &Vec3::new(4.0, 2.0, 0.0)));
+    assert_eq!(Some(Vec3::new(1.0, 0.5, 0.0)), plane.intersect_segment(&Vec3::new(4.0, 2.0, 0.0), &Vec3::new(0.0, 0.0, 0.0)));
+    assert_eq!(Some(Vec3::new(1.0, 3.0, 0.0)), plane.intersect_segment(&Vec3::new(1.0, 3.0, 0.0), &Vec3::new(4.0, 2.0, 0.0)));
+
+    assert_eq!(None, plane.intersect_segment(&Vec3::new(2.0, 0.0, 0.0), &Vec3::new(4.0, 2.0, 0.0)));
+    assert_eq!(None, plane.intersect_segment(&Vec3::new(1.0, 0.0, 0.0), &Vec3::new(1.0, 2.0, 0.0)));
+}
+
+#[test]
+fn frustum_can_be_extracted_from_a_projection() {
+    let projection: Matrix44<f64> = Matrix44::new_frustum(-1.0, 1.0, -1.0, 1.0, 1.0, 10.0);
+    let frustum = Frustum::from_matrix(&projection).unwrap();
+
+    assert!(frustum.contains_point(&Vec3::new(0.0, 0.0, -5.0)));
+    assert!(frustum.contains_point(&Vec3::new(1.9, -1.9, -2.0)));
+    assert!(!frustum.contains_point(&Vec3::new(2.1, 0.0, -2.0)));
+    assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -0.5)));
+    assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -11.0)));
+    assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, 5.0)));
+
+    /* Planes are normalized, so distances are in world units. */
+    assert_delta!(frustum.planes[4].distance(&Vec3::new(0.0, 0.0, -3.0)), 2.0, 0.0001);
+
+    assert!(frustum.intersects_aabb(&Aabb::new(Vec3::new(1.5, -0.5, -3.0), Vec3::new(10.0, 0.5, -2.0))));
+    assert!(!frustum.intersects_aabb(&Aabb::new(Vec3::new(-1.0, -1.0, 1.0), Vec3::new(1.0, 1.0, 2.0))));
+    assert!(!frustum.intersects_aabb(&Aabb::new(Vec3::new(5.0, -1.0, -3.0), Vec3::new(6.0, 1.0, -2.0))));
+
+    assert_eq!(None, Frustum::from_matrix(&Matrix44::new_identity().scale(0.0)));
+}
+
+#[test]
+fn triangle_has_normal_area_and_centroid() {
+    let triangle = Triangle::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 3.0, 0.0), Vec3::new(0.0, 0.0, 6.0));
+    assert_eq!(Vec3::new(1.0, 0.0, 0.0), triangle.normal());
+    assert_delta!(triangle.area(), 9.0, 0.0001);
+    assert_eq!(Vec3::new(0.0, 1.0, 2.0), triangle.centroid());
+    assert_eq!(Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 3.0, 6.0)), triangle.aabb());
+
+    let degenerate = Triangle::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Vec3::new(2.0, 2.0, 2.0));
+    assert_eq!(Vec3::new(0.0, 0.0, 0.0), degenerate.normal());
+    assert_delta!(degenerate.area(), 0.0, 0.0001);
+}
+
+#[test]
+fn triangle_can_be_converted_from_an_object_triangle() {
+    use wavefront::parse_object;
+
+    let object = parse_object("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+f 1 2 3
+").unwrap();
+    assert_eq!(unit_triangle(), Triangle::from(&object.triangles[0]));
+}
@@ -1,144 +1,252 @@
 extern crate num;
 use std::ops::{Mul};
-use std::mem;
 use std::ops::{Index, IndexMut};
 
 use geometry::vector::*;
 
-#[derive(Debug, Clone)]
+/* Stored row by row: m[i][j] is row i, column j, so m[3] holds the
+   translation under the row vector convention. */
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Matrix44<T: num::Float> {
-    m11: T, m12: T, m13: T, m14: T,
-    m21: T, m22: T, m23: T, m24: T,
-    m31: T, m32: T, m33: T, m34: T,
-    m41: T, m42: T, m43: T, m44: T,
+    m: [[T; 4]; 4],
 }
 
 impl<T: num::Float> Matrix44<T> {
     pub fn new(m11: T, m12: T, m13: T, m14: T, m21: T, m22: T, m23: T, m24: T, m31: T, m32: T, m33: T, m34: T, m41: T, m42: T, m43: T, m44: T) -> Matrix44<T> {
-        Matrix44{m11 : m11, m12 : m12, m13 : m13, m14 : m14,
-                 m21 : m21, m22 : m22, m23 : m23, m24 : m24,
-                 m31 : m31, m32 : m32, m33 : m33, m34 : m34,
-                 m41 : m41, m42 : m42, m43 : m43, m44 : m44}
+        Matrix44{m: [[m11, m12, m13, m14],
+                     [m21, m22, m23, m24],
+                     [m31, m32, m33, m34],
+                     [m41, m42, m43, m44]]}
     }
 
     fn new_single(m: T) -> Matrix44<T> {
-        Matrix44{m11 : m, m12 : m, m13 : m, m14 : m,
-                 m21 : m, m22 : m, m23 : m, m24 : m,
-                 m31 : m, m32 : m, m33 : m, m34 : m,
-                 m41 : m, m42 : m, m43 : m, m44 : m}
+        Matrix44{m: [[m; 4]; 4]}
     }
 
-    fn new_identity() -> Matrix44<T> {
+    pub fn new_identity() -> Matrix44<T> {
         let zero: T = num::cast::<f64, T>(0.0).unwrap();
         let one: T = num::cast::<f64, T>(1.0).unwrap();
 
-        Matrix44{m11 : one, m12 : zero, m13 : zero, m14 : zero,
-                 m21 : zero, m22 : one, m23 : zero, m24 : zero,
-                 m31 : zero, m32 : zero, m33 : one, m34 : zero,
-                 m41 : zero, m42 : zero, m43 : zero, m44 : one}
+        Matrix44{m: [[one, zero, zero, zero],
+                     [zero, one, zero, zero],
+                     [zero, zero, one, zero],
+                     [zero, zero, zero, one]]}
     }
 
-    fn transpose(self) -> Matrix44<T> {
-        let value: T = num::cast::<f64, T>(0.0).unwrap();
-        let mut matrix : Matrix44<T> = Matrix44::new_single(value);
-        
-        for i in 0..4 {
-            for j in 0..4 {
-                matrix[(i, j)] = self[(j, i)]
-            }
-        }
+    /* The constructors below follow the row vector convention used by
+       multVecMatrix: a point is transformed as p * M, so the translation
+       lives in the fourth row, and A * B applies A first and then B. */
+
+    pub fn new_translation(x: T, y: T, z: T) -> Matrix44<T> {
+        let mut matrix = Matrix44::new_identity();
+        matrix[(3, 0)] = x;
+        matrix[(3, 1)] = y;
+        matrix[(3, 2)] = z;
 
         matrix
     }
 
-    pub fn inverse(self) -> Matrix44<T> {
-        let value: T = num::cast::<f64, T>(0.0).unwrap();
+    pub fn new_scaling(x: T, y: T, z: T) -> Matrix44<T> {
+        let mut matrix = Matrix44::new_identity();
+        matrix[(0, 0)] = x;
+        matrix[(1, 1)] = y;
+        matrix[(2, 2)] = z;
+
+        matrix
+    }
+
+    /* Rotations are counter-clockwise, in radians, when looking down the
+       axis towards the origin. */
+    pub fn new_rotation_x(angle: T) -> Matrix44<T> {
+        let (sin, cos) = (angle.sin(), angle.cos());
+        let mut matrix = Matrix44::new_identity();
+        matrix[(1, 1)] = cos;
+        matrix[(1, 2)] = sin;
+        matrix[(2, 1)] = -sin;
+        matrix[(2, 2)] = cos;
+
+        matrix
+    }
+
+    pub fn new_rotation_y(angle: T) -> Matrix44<T> {
+        let (sin, cos) = (angle.sin(), angle.cos());
+        let mut matrix = Matrix44::new_identity();
+        matrix[(0, 0)] = cos;
+        matrix[(0, 2)] = -sin;
+        matrix[(2, 0)] = sin;
+        matrix[(2, 2)] = cos;
+
+        matrix
+    }
+
+    pub fn new_rotation_z(angle: T) -> Matrix44<T> {
+        let (sin, cos) = (angle.sin(), angle.cos());
+        let mut matrix = Matrix44::new_identity();
+        matrix[(0, 0)] = cos;
+        matrix[(0, 1)] = sin;
+        matrix[(1, 0)] = -sin;
+        matrix[(1, 1)] = cos;
+
+        matrix
+    }
+
+    /* Rotation about an arbitrary axis through the origin (Rodrigues'
+       formula). The axis does not need to be normalized. */
+    pub fn new_rotation(axis: &Vec3<T>, angle: T) -> Matrix44<T> {
         let one: T = num::cast::<f64, T>(1.0).unwrap();
-        let mut matrix : Matrix44<T> = Matrix44::new_single(value);
-        
-        let det = self[(0, 3)]*self[(1, 2)]*self[(2, 1)]*self[(3, 0)] - self[(0, 2)]*self[(1, 3)]*self[(2, 1)]*self[(3, 0)] - self[(0, 3)]*self[(1, 1)]*self[(2, 2)]*self[(3, 0)] + self[(0, 1)]*self[(1, 3)]*self[(2, 2)]*self[(3, 0)] +
+        let u = axis.normalize();
+        let (sin, cos) = (angle.sin(), angle.cos());
+        let t = one - cos;
+        let mut matrix = Matrix44::new_identity();
+
+        matrix[(0, 0)] = cos + u.x * u.x * t;
+        matrix[(0, 1)] = u.x * u.y * t + u.z * sin;
+        matrix[(0, 2)] = u.x * u.z * t - u.y * sin;
+        matrix[(1, 0)] = u.y * u.x * t - u.z * sin;
+        matrix[(1, 1)] = cos + u.y * u.y * t;
+        matrix[(1, 2)] = u.y * u.z * t + u.x * sin;
+        matrix[(2, 0)] = u.z * u.x * t + u.y * sin;
+        matrix[(2, 1)] = u.z * u.y * t - u.x * sin;
+        matrix[(2, 2)] = cos + u.z * u.z * t;
+
+        matrix
+    }
+
+    /* Camera to world matrix for a camera at eye looking at target. The
+       camera looks down its negative z axis, with up roughly along y. */
+    pub fn new_look_at(eye: &Vec3<T>, target: &Vec3<T>, up: &Vec3<T>) -> Matrix44<T> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+        let forward = (eye - target).normalize();
+        let right = up.cross_product(forward).normalize();
+        let true_up = forward.cross_product(right);
+
+        Matrix44::new(right.x, right.y, right.z, zero,
+                      true_up.x, true_up.y, true_up.z, zero,
+                      forward.x, forward.y, forward.z, zero,
+                      eye.x, eye.y, eye.z, one)
+    }
+
+    /* Projection matrices, equivalent to OpenGL's glFrustum, glOrtho and
+       gluPerspective but transposed for row vectors. They take points in
+       camera space, where the camera looks down -z, to clip space; the
+       divide by w in multVecMatrix then gives normalized device coordinates
+       in [-1, 1], with the near plane at z = -1 and the far plane at z = 1. */
+
+    pub fn new_frustum(left: T, right: T, bottom: T, top: T, near: T, far: T) -> Matrix44<T> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+        let two: T = num::cast::<f64, T>(2.0).unwrap();
+        let mut matrix = Matrix44::new_single(zero);
+
+        matrix[(0, 0)] = two * near / (right - left);
+        matrix[(1, 1)] = two * near / (top - bottom);
+        matrix[(2, 0)] = (right + left) / (right - left);
+        matrix[(2, 1)] = (top + bottom) / (top - bottom);
+        matrix[(2, 2)] = -(far + near) / (far - near);
+        matrix[(2, 3)] = -one;
+        matrix[(3, 2)] = -two * far * near / (far - near);
+
+        matrix
+    }
+
+    /* fovy is the vertical field of view in radians, and aspect is width
+       divided by height. */
+    pub fn new_perspective(fovy: T, aspect: T, near: T, far: T) -> Matrix44<T> {
+        let two: T = num::cast::<f64, T>(2.0).unwrap();
+        let top = near * (fovy / two).tan();
+        let right = top * aspect;
+
+        Matrix44::new_frustum(-right, right, -top, top, near, far)
+    }
+
+    pub fn new_orthographic(left: T, right: T, bottom: T, top: T, near: T, far: T) -> Matrix44<T> {
+        let two: T = num::cast::<f64, T>(2.0).unwrap();
+        let mut matrix = Matrix44::new_identity();
+
+        matrix[(0, 0)] = two / (right - left);
+        matrix[(1, 1)] = two / (top - bottom);
+        matrix[(2, 2)] = -two / (far - near);
+        matrix[(3, 0)] = -(right + left) / (right - left);
+        matrix[(3, 1)] = -(top + bottom) / (top - bottom);
+        matrix[(3, 2)] = -(far + near) / (far - near);
+
+        matrix
+    }
+
+    pub fn row(&self, i: usize) -> [T; 4] {
+        self.m[i]
+    }
+
+    pub fn column(&self, j: usize) -> [T; 4] {
+        [self.m[0][j], self.m[1][j], self.m[2][j], self.m[3][j]]
+    }
+
+    pub fn transpose(self) -> Matrix44<T> {
+        Matrix44{m: [self.column(0), self.column(1), self.column(2), self.column(3)]}
+    }
+
+    pub fn determinant(&self) -> T {
+        self[(0, 3)]*self[(1, 2)]*self[(2, 1)]*self[(3, 0)] - self[(0, 2)]*self[(1, 3)]*self[(2, 1)]*self[(3, 0)] - self[(0, 3)]*self[(1, 1)]*self[(2, 2)]*self[(3, 0)] + self[(0, 1)]*self[(1, 3)]*self[(2, 2)]*self[(3, 0)] +
                   self[(0, 2)]*self[(1, 1)]*self[(2, 3)]*self[(3, 0)] - self[(0, 1)]*self[(1, 2)]*self[(2, 3)]*self[(3, 0)] - self[(0, 3)]*self[(1, 2)]*self[(2, 0)]*self[(3, 1)] + self[(0, 2)]*self[(1, 3)]*self[(2, 0)]*self[(3, 1)] +
                   self[(0, 3)]*self[(1, 0)]*self[(2, 2)]*self[(3, 1)] - self[(0, 0)]*self[(1, 3)]*self[(2, 2)]*self[(3, 1)] - self[(0, 2)]*self[(1, 0)]*self[(2, 3)]*self[(3, 1)] + self[(0, 0)]*self[(1, 2)]*self[(2, 3)]*self[(3, 1)] +
                   self[(0, 3)]*self[(1, 1)]*self[(2, 0)]*self[(3, 2)] - self[(0, 1)]*self[(1, 3)]*self[(2, 0)]*self[(3, 2)] - self[(0, 3)]*self[(1, 0)]*self[(2, 1)]*self[(3, 2)] + self[(0, 0)]*self[(1, 3)]*self[(2, 1)]*self[(3, 2)] +
                   self[(0, 1)]*self[(1, 0)]*self[(2, 3)]*self[(3, 2)] - self[(0, 0)]*self[(1, 1)]*self[(2, 3)]*self[(3, 2)] - self[(0, 2)]*self[(1, 1)]*self[(2, 0)]*self[(3, 3)] + self[(0, 1)]*self[(1, 2)]*self[(2, 0)]*self[(3, 3)] +
-                  self[(0, 2)]*self[(1, 0)]*self[(2, 1)]*self[(3, 3)] - self[(0, 0)]*self[(1, 2)]*self[(2, 1)]*self[(3, 3)] - self[(0, 1)]*self[(1, 0)]*self[(2, 2)]*self[(3, 3)] + self[(0, 0)]*self[(1, 1)]*self[(2, 2)]*self[(3, 3)];
-
-        matrix[(0, 0)] = self[(1, 2)]*self[(2, 3)]*self[(3, 1)] - self[(1, 3)]*self[(2, 2)]*self[(3, 1)] + self[(1, 3)]*self[(2, 1)]*self[(3, 2)] - self[(1, 1)]*self[(2, 3)]*self[(3, 2)] - self[(1, 2)]*self[(2, 1)]*self[(3, 3)] + self[(1, 1)]*self[(2, 2)]*self[(3, 3)];
-        matrix[(0, 1)] = self[(0, 3)]*self[(2, 2)]*self[(3, 1)] - self[(0, 2)]*self[(2, 3)]*self[(3, 1)] - self[(0, 3)]*self[(2, 1)]*self[(3, 2)] + self[(0, 1)]*self[(2, 3)]*self[(3, 2)] + self[(0, 2)]*self[(2, 1)]*self[(3, 3)] - self[(0, 1)]*self[(2, 2)]*self[(3, 3)];
-        matrix[(0, 2)] = self[(0, 2)]*self[(1, 3)]*self[(3, 1)] - self[(0, 3)]*self[(1, 2)]*self[(3, 1)] + self[(0, 3)]*self[(1, 1)]*self[(3, 2)] - self[(0, 1)]*self[(1, 3)]*self[(3, 2)] - self[(0, 2)]*self[(1, 1)]*self[(3, 3)] + self[(0, 1)]*self[(1, 2)]*self[(3, 3)];
-        matrix[(0, 3)] = self[(0, 3)]*self[(1, 2)]*self[(2, 1)] - self[(0, 2)]*self[(1, 3)]*self[(2, 1)] - self[(0, 3)]*self[(1, 1)]*self[(2, 2)] + self[(0, 1)]*self[(1, 3)]*self[(2, 2)] + self[(0, 2)]*self[(1, 1)]*self[(2, 3)] - self[(0, 1)]*self[(1, 2)]*self[(2, 3)];
-        matrix[(1, 0)] = self[(1, 3)]*self[(2, 2)]*self[(3, 0)] - self[(1, 2)]*self[(2, 3)]*self[(3, 0)] - self[(1, 3)]*self[(2, 0)]*self[(3, 2)] + self[(1, 0)]*self[(2, 3)]*self[(3, 2)] + self[(1, 2)]*self[(2, 0)]*self[(3, 3)] - self[(1, 0)]*self[(2, 2)]*self[(3, 3)];
-        matrix[(1, 1)] = self[(0, 2)]*self[(2, 3)]*self[(3, 0)] - self[(0, 3)]*self[(2, 2)]*self[(3, 0)] + self[(0, 3)]*self[(2, 0)]*self[(3, 2)] - self[(0, 0)]*self[(2, 3)]*self[(3, 2)] - self[(0, 2)]*self[(2, 0)]*self[(3, 3)] + self[(0, 0)]*self[(2, 2)]*self[(3, 3)];
-        matrix[(1, 2)] = self[(0, 3)]*self[(1, 2)]*self[(3, 0)] - self[(0, 2)]*self[(1, 3)]*self[(3, 0)] - self[(0, 3)]*self[(1, 0)]*self[(3, 2)] + self[(0, 0)]*self[(1, 3)]*self[(3, 2)] + self[(0, 2)]*self[(1, 0)]*self[(3, 3)] - self[(0, 0)]*self[(1, 2)]*self[(3, 3)];
-        matrix[(1, 3)] = self[(0, 2)]*self[(1, 3)]*self[(2, 0)] - self[(0, 3)]*self[(1, 2)]*self[(2, 0)] + self[(0, 3)]*self[(1, 0)]*self[(2, 2)] - self[(0, 0)]*self[(1, 3)]*self[(2, 2)] - self[(0, 2)]*self[(1, 0)]*self[(2, 3)] + self[(0, 0)]*self[(1, 2)]*self[(2, 3)];
-        matrix[(2, 0)] = self[(1, 1)]*self[(2, 3)]*self[(3, 0)] - self[(1, 3)]*self[(2, 1)]*self[(3, 0)] + self[(1, 3)]*self[(2, 0)]*self[(3, 1)] - self[(1, 0)]*self[(2, 3)]*self[(3, 1)] - self[(1, 1)]*self[(2, 0)]*self[(3, 3)] + self[(1, 0)]*self[(2, 1)]*self[(3, 3)];
-        matrix[(2, 1)] = self[(0, 3)]*self[(2, 1)]*self[(3, 0)] - self[(0, 1)]*self[(2, 3)]*self[(3, 0)] - self[(0, 3)]*self[(2, 0)]*self[(3, 1)] + self[(0, 0)]*self[(2, 3)]*self[(3, 1)] + self[(0, 1)]*self[(2, 0)]*self[(3, 3)] - self[(0, 0)]*self[(2, 1)]*self[(3, 3)];
-        matrix[(2, 2)] = self[(0, 1)]*self[(1, 3)]*self[(3, 0)] - self[(0, 3)]*self[(1, 1)]*self[(3, 0)] + self[(0, 3)]*self[(1, 0)]*self[(3, 1)] - self[(0, 0)]*self[(1, 3)]*self[(3, 1)] - self[(0, 1)]*self[(1, 0)]*self[(3, 3)] + self[(0, 0)]*self[(1, 1)]*self[(3, 3)];
-        matrix[(2, 3)] = self[(0, 3)]*self[(1, 1)]*self[(2, 0)] - self[(0, 1)]*self[(1, 3)]*self[(2, 0)] - self[(0, 3)]*self[(1, 0)]*self[(2, 1)] + self[(0, 0)]*self[(1, 3)]*self[(2, 1)] + self[(0, 1)]*self[(1, 0)]*self[(2, 3)] - self[(0, 0)]*self[(1, 1)]*self[(2, 3)];
-        matrix[(3, 0)] = self[(1, 2)]*self[(2, 1)]*self[(3, 0)] - self[(1, 1)]*self[(2, 2)]*self[(3, 0)] - self[(1, 2)]*self[(2, 0)]*self[(3, 1)] + self[(1, 0)]*self[(2, 2)]*self[(3, 1)] + self[(1, 1)]*self[(2, 0)]*self[(3, 2)] - self[(1, 0)]*self[(2, 1)]*self[(3, 2)];
-        matrix[(3, 1)] = self[(0, 1)]*self[(2, 2)]*self[(3, 0)] - self[(0, 2)]*self[(2, 1)]*self[(3, 0)] + self[(0, 2)]*self[(2, 0)]*self[(3, 1)] - self[(0, 0)]*self[(2, 2)]*self[(3, 1)] - self[(0, 1)]*self[(2, 0)]*self[(3, 2)] + self[(0, 0)]*self[(2, 1)]*self[(3, 2)];
-        matrix[(3, 2)] = self[(0, 2)]*self[(1, 1)]*self[(3, 0)] - self[(0, 1)]*self[(1, 2)]*self[(3, 0)] - self[(0, 2)]*self[(1, 0)]*self[(3, 1)] + self[(0, 0)]*self[(1, 2)]*self[(3, 1)] + self[(0, 1)]*self[(1, 0)]*self[(3, 2)] - self[(0, 0)]*self[(1, 1)]*self[(3, 2)];
-        matrix[(3, 3)] = self[(0, 1)]*self[(1, 2)]*self[(2, 0)] - self[(0, 2)]*self[(1, 1)]*self[(2, 0)] + self[(0, 2)]*self[(1, 0)]*self[(2, 1)] - self[(0, 0)]*self[(1, 2)]*self[(2, 1)] - self[(0, 1)]*self[(1, 0)]*self[(2, 2)] + self[(0, 0)]*self[(1, 1)]*self[(2, 2)];
-
-        let scaled_matrix = matrix.scale(one/det);
-
-        scaled_matrix
+                  self[(0, 2)]*self[(1, 0)]*self[(2, 1)]*self[(3, 3)] - self[(0, 0)]*self[(1, 2)]*self[(2, 1)]*self[(3, 3)] - self[(0, 1)]*self[(1, 0)]*self[(2, 2)]*self[(3, 3)] + self[(0, 0)]*self[(1, 1)]*self[(2, 2)]*self[(3, 3)]
     }
 
-    pub fn inverse2(self) -> Matrix44<T> {
+    /* Inverts the matrix by Gauss Jordan elimination with partial pivoting.
+       Returns None when the matrix is singular, which is when the largest
+       remaining value in a column is negligible next to the largest value
+       in the matrix, or when the matrix has infinite or NaN values. */
+    pub fn try_inverse(&self) -> Option<Matrix44<T>> {
         let zero: T = num::cast::<f64, T>(0.0).unwrap();
-        let mut inverse : Matrix44<T> = self;
+        let sixteen: T = num::cast::<f64, T>(16.0).unwrap();
+        let mut inverse : Matrix44<T> = *self;
         let mut identity : Matrix44<T> = Matrix44::new_identity();
 
-        /* Gauss Jordan elimination */
-        for k in 0..4 {
-
-
-            /*** If row k has a 0 at column k, then we need to swap it
-                 with a different row so that we have a non zero value
-                 at [(k, k)]. */
+        let mut largest = zero;
+        for i in 0..4 {
+            for j in 0..4 {
+                if !self[(i, j)].is_finite() {
+                    return None;
+                }
+                largest = largest.max(self[(i, j)].abs());
+            }
+        }
+        let epsilon = largest * T::epsilon() * sixteen;
 
-            let mut row_with_nonzero_kk = k;
+        for k in 0..4 {
+            /*** Partial pivoting: swap the row with the largest value in
+                 column k into row k, which keeps the division below stable. */
 
-            /* Find a row that has a value in column k. */
-            while row_with_nonzero_kk < 4 {
-                if inverse[(row_with_nonzero_kk, k)] != zero {
-                    break
+            let mut pivot_row = k;
+            for row in k + 1..4 {
+                if inverse[(row, k)].abs() > inverse[(pivot_row, k)].abs() {
+                    pivot_row = row;
                 }
-                row_with_nonzero_kk = row_with_nonzero_kk + 1
             }
 
-            /* If there were no rows with a value in column k, we can't invert this matrix. */
-            if row_with_nonzero_kk == 4 {
-                panic!();
+            /* If there is no usable value in column k, we can't invert this matrix. */
+            if inverse[(pivot_row, k)].abs() <= epsilon {
+                return None;
             }
 
-            /* If we need to, swap the rows k and row_with_nonzero_kk to get the row with a value in column k
-               into row k. */
-            if(row_with_nonzero_kk != k) {
+            if pivot_row != k {
                 for j in 0..4 {
-                    let mut temp = zero;
-
-                    temp = inverse[(row_with_nonzero_kk, j)];
-                    inverse[(row_with_nonzero_kk, j)] = inverse[(k, j)];
+                    let temp = inverse[(pivot_row, j)];
+                    inverse[(pivot_row, j)] = inverse[(k, j)];
                     inverse[(k, j)] = temp;
 
-                    temp = identity[(row_with_nonzero_kk, j)];
-                    identity[(row_with_nonzero_kk, j)] = identity[(k, j)];
+                    let temp = identity[(pivot_row, j)];
+                    identity[(pivot_row, j)] = identity[(k, j)];
                     identity[(k, j)] = temp;
                 }
             }
 
-
-
             /*** Scale row k so that position [(k, k)] is equal to 1.0 */
 
             let pivot = inverse[(k, k)];
-
-            /* Use the pivot value to scale the value at [k][k] to 1, by scaling 
-               the entire row. */
-            /* Todo: Do we want to skip the left columns of row k, because they should
-               be 0? */
             for col in 0..4 {
                 inverse[(k, col)] = inverse[(k, col)] / pivot;
                 identity[(k, col)] = identity[(k, col)] / pivot;
@@ -159,10 +267,10 @@ impl<T: num::Float> Matrix44<T> {
             }
         }
 
-        identity
+        Some(identity)
     }
 
-    fn scale(self, scale: T) -> Matrix44<T> {
+    pub fn scale(self, scale: T) -> Matrix44<T> {
         let value: T = num::cast::<f64, T>(0.0).unwrap();
         let mut matrix : Matrix44<T> = Matrix44::new_single(value);
 
@@ -197,17 +305,25 @@ impl<T: num::Float> Index<(usize, usize)> for Matrix44<T> {
     type Output = T;
 
     fn index(&self, (i, j): (usize, usize)) -> &T {
-        unsafe {
-            &mem::transmute::<&Matrix44<T>, & [T; 4 * 4]>(self)[4 * i + j]
-        }
+        &self.m[i][j]
     }
 }
 
 impl<T: num::Float> IndexMut<(usize, usize)> for Matrix44<T> {
     fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
-        unsafe {
-            &mut mem::transmute::<&mut Matrix44<T>, &mut [T; 4 * 4]>(self)[4 * i + j]
-        }
+        &mut self.m[i][j]
+    }
+}
+
+impl<T: num::Float> From<[[T; 4]; 4]> for Matrix44<T> {
+    fn from(m: [[T; 4]; 4]) -> Matrix44<T> {
+        Matrix44{m}
+    }
+}
+
+impl<T: num::Float> From<Matrix44<T>> for [[T; 4]; 4] {
+    fn from(matrix: Matrix44<T>) -> [[T; 4]; 4] {
+        matrix.m
     }
 }
 
@@ -232,35 +348,64 @@ impl<T: num::Float> Mul for Matrix44<T> {
 
 
 
-macro_rules! assert_delta {
-    ($x:expr, $y:expr, $d:expr) => {
-        if $x - $y >= $d || $y - $x >= $d { panic!("x: {:?}, y: {:?}, d: {:?}", $x, $y, $d); }
-    }
-}
-
 fn assert_eq_float_range<T: num::Float>(n1: T, n2: T, range: T) {
     assert!((n1 - n2).abs() < range.abs());
 }
 
+#[cfg(test)]
+fn assert_matrix_delta(actual: &Matrix44<f64>, expected: &Matrix44<f64>, delta: f64) {
+    for i in 0..4 {
+        for j in 0..4 {
+            if (actual[(i, j)] - expected[(i, j)]).abs() >= delta {
+                panic!("at ({}, {}): actual: {:?}, expected: {:?}", i, j, actual, expected);
+            }
+        }
+    }
+}
+
 #[test]
 fn matrix44_can_be_created() {
     let mat1: Matrix44<f64> = Matrix44::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0);
-    assert_delta!(mat1.m11, 1.0, 0.001);
-    assert_delta!(mat1.m12, 2.0, 0.001);
-    assert_delta!(mat1.m13, 3.0, 0.001);
-    assert_delta!(mat1.m14, 4.0, 0.001);
-    assert_delta!(mat1.m21, 5.0, 0.001);
-    assert_delta!(mat1.m22, 6.0, 0.001);
-    assert_delta!(mat1.m23, 7.0, 0.001);
-    assert_delta!(mat1.m24, 8.0, 0.001);
-    assert_delta!(mat1.m31, 9.0, 0.001);
-    assert_delta!(mat1.m32, 10.0, 0.001);
-    assert_delta!(mat1.m33, 11.0, 0.001);
-    assert_delta!(mat1.m34, 12.0, 0.001);
-    assert_delta!(mat1.m41, 13.0, 0.001);
-    assert_delta!(mat1.m42, 14.0, 0.001);
-    assert_delta!(mat1.m43, 15.0, 0.001);
-    assert_delta!(mat1.m44, 16.0, 0.001);
+    assert_delta!(mat1[(0, 0)], 1.0, 0.001);
+    assert_delta!(mat1[(0, 1)], 2.0, 0.001);
+    assert_delta!(mat1[(0, 2)], 3.0, 0.001);
+    assert_delta!(mat1[(0, 3)], 4.0, 0.001);
+    assert_delta!(mat1[(1, 0)], 5.0, 0.001);
+    assert_delta!(mat1[(1, 1)], 6.0, 0.001);
+    assert_delta!(mat1[(1, 2)], 7.0, 0.001);
+    assert_delta!(mat1[(1, 3)], 8.0, 0.001);
+    assert_delta!(mat1[(2, 0)], 9.0, 0.001);
+    assert_delta!(mat1[(2, 1)], 10.0, 0.001);
+    assert_delta!(mat1[(2, 2)], 11.0, 0.001);
+    assert_delta!(mat1[(2, 3)], 12.0, 0.001);
+    assert_delta!(mat1[(3, 0)], 13.0, 0.001);
+    assert_delta!(mat1[(3, 1)], 14.0, 0.001);
+    assert_delta!(mat1[(3, 2)], 15.0, 0.001);
+    assert_delta!(mat1[(3, 3)], 16.0, 0.001);
+}
+
+#[test]
+fn matrix44_can_be_converted_from_and_to_arrays() {
+    let array = [[1.0, 2.0, 3.0, 4.0], [5.0, 6.0, 7.0, 8.0], [9.0, 10.0, 11.0, 12.0], [13.0, 14.0, 15.0, 16.0]];
+    let mat1: Matrix44<f64> = Matrix44::from(array);
+    assert_eq!(Matrix44::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0), mat1);
+    assert_delta!(mat1[(1, 2)], 7.0, 0.001);
+
+    let back: [[f64; 4]; 4] = mat1.into();
+    assert_eq!(array, back);
+}
+
+#[test]
+fn matrix44_has_rows_and_columns() {
+    let mut mat1: Matrix44<f64> = Matrix44::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0);
+    assert_eq!([5.0, 6.0, 7.0, 8.0], mat1.row(1));
+    assert_eq!([3.0, 7.0, 11.0, 15.0], mat1.column(2));
+
+    /* Copies are independent of the original. */
+    let copy = mat1;
+    mat1[(3, 3)] = 0.0;
+    assert_eq!([13.0, 14.0, 15.0, 0.0], mat1.row(3));
+    assert_eq!([13.0, 14.0, 15.0, 16.0], copy.row(3));
 }
 
 #[test]
@@ -268,110 +413,253 @@ fn matrix44_can_be_multiplied() {
     let mat1: Matrix44<f64> = Matrix44::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0);
     let mat2: Matrix44<f64> = Matrix44::new(1.1, 2.2, 3.3, 4.4, 5.5, 6.6, 7.7, 8.8, 9.9, 10.10, 11.11, 12.12, 13.13, 14.14, 15.15, 16.16);
     let mat3 = mat1 * mat2;
-    assert_delta!(mat3.m11, 94.32, 0.001);
-    assert_delta!(mat3.m12, 102.26, 0.001);
-    assert_delta!(mat3.m13, 112.63, 0.001);
-    assert_delta!(mat3.m14, 123.0, 0.001);
-    assert_delta!(mat3.m21, 212.84, 0.001);
-    assert_delta!(mat3.m22, 234.42, 0.001);
-    assert_delta!(mat3.m23, 261.67, 0.001);
-    assert_delta!(mat3.m24, 288.92, 0.001);
-    assert_delta!(mat3.m31, 331.36, 0.001);
-    assert_delta!(mat3.m32, 366.58, 0.001);
-    assert_delta!(mat3.m33, 410.71, 0.001);
-    assert_delta!(mat3.m34, 454.84, 0.001);
-    assert_delta!(mat3.m41, 449.88, 0.001);
-    assert_delta!(mat3.m42, 498.74, 0.001);
-    assert_delta!(mat3.m43, 559.75, 0.001);
-    assert_delta!(mat3.m44, 620.76, 0.001);
+    assert_delta!(mat3[(0, 0)], 94.32, 0.001);
+    assert_delta!(mat3[(0, 1)], 102.26, 0.001);
+    assert_delta!(mat3[(0, 2)], 112.63, 0.001);
+    assert_delta!(mat3[(0, 3)], 123.0, 0.001);
+    assert_delta!(mat3[(1, 0)], 212.84, 0.001);
+    assert_delta!(mat3[(1, 1)], 234.42, 0.001);
+    assert_delta!(mat3[(1, 2)], 261.67, 0.001);
+    assert_delta!(mat3[(1, 3)], 288.92, 0.001);
+    assert_delta!(mat3[(2, 0)], 331.36, 0.001);
+    assert_delta!(mat3[(2, 1)], 366.58, 0.001);
+    assert_delta!(mat3[(2, 2)], 410.71, 0.001);
+    assert_delta!(mat3[(2, 3)], 454.84, 0.001);
+    assert_delta!(mat3[(3, 0)], 449.88, 0.001);
+    assert_delta!(mat3[(3, 1)], 498.74, 0.001);
+    assert_delta!(mat3[(3, 2)], 559.75, 0.001);
+    assert_delta!(mat3[(3, 3)], 620.76, 0.001);
 }
 
 #[test]
 fn matrix44_can_be_transposed() {
     let mat1: Matrix44<f64> = Matrix44::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0);
     let mat2 = mat1.transpose();
-    assert_delta!(mat2.m11, 1.0, 0.001);
-    assert_delta!(mat2.m12, 5.0, 0.001);
-    assert_delta!(mat2.m13, 9.0, 0.001);
-    assert_delta!(mat2.m14, 13.0, 0.001);
-    assert_delta!(mat2.m21, 2.0, 0.001);
-    assert_delta!(mat2.m22, 6.0, 0.001);
-    assert_delta!(mat2.m23, 10.0, 0.001);
-    assert_delta!(mat2.m24, 14.0, 0.001);
-    assert_delta!(mat2.m31, 3.0, 0.001);
-    assert_delta!(mat2.m32, 7.0, 0.001);
-    assert_delta!(mat2.m33, 11.0, 0.001);
-    assert_delta!(mat2.m34, 15.0, 0.001);
-    assert_delta!(mat2.m41, 4.0, 0.001);
-    assert_delta!(mat2.m42, 8.0, 0.001);
-    assert_delta!(mat2.m43, 12.0, 0.001);
-    assert_delta!(mat2.m44, 16.0, 0.001);
+    assert_delta!(mat2[(0, 0)], 1.0, 0.001);
+    assert_delta!(mat2[(0, 1)], 5.0, 0.001);
+    assert_delta!(mat2[(0, 2)], 9.0, 0.001);
+    assert_delta!(mat2[(0, 3)], 13.0, 0.001);
+    assert_delta!(mat2[(1, 0)], 2.0, 0.001);
+    assert_delta!(mat2[(1, 1)], 6.0, 0.001);
+    assert_delta!(mat2[(1, 2)], 10.0, 0.001);
+    assert_delta!(mat2[(1, 3)], 14.0, 0.001);
+    assert_delta!(mat2[(2, 0)], 3.0, 0.001);
+    assert_delta!(mat2[(2, 1)], 7.0, 0.001);
+    assert_delta!(mat2[(2, 2)], 11.0, 0.001);
+    assert_delta!(mat2[(2, 3)], 15.0, 0.001);
+    assert_delta!(mat2[(3, 0)], 4.0, 0.001);
+    assert_delta!(mat2[(3, 1)], 8.0, 0.001);
+    assert_delta!(mat2[(3, 2)], 12.0, 0.001);
+    assert_delta!(mat2[(3, 3)], 16.0, 0.001);
 }
 
 #[test]
 fn matrix44_can_be_scaled() {
     let mat1: Matrix44<f64> = Matrix44::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0);
     let mat2 = mat1.scale(0.25);
-    assert_delta!(mat2.m11, 0.25, 0.001);
-    assert_delta!(mat2.m12, 0.5, 0.001);
-    assert_delta!(mat2.m13, 0.75, 0.001);
-    assert_delta!(mat2.m14, 1.0, 0.001);
-    assert_delta!(mat2.m21, 1.25, 0.001);
-    assert_delta!(mat2.m22, 1.5, 0.001);
-    assert_delta!(mat2.m23, 1.75, 0.001);
-    assert_delta!(mat2.m24, 2.0, 0.001);
-    assert_delta!(mat2.m31, 2.25, 0.001);
-    assert_delta!(mat2.m32, 2.5, 0.001);
-    assert_delta!(mat2.m33, 2.75, 0.001);
-    assert_delta!(mat2.m34, 3.0, 0.001);
-    assert_delta!(mat2.m41, 3.25, 0.001);
-    assert_delta!(mat2.m42, 3.5, 0.001);
-    assert_delta!(mat2.m43, 3.75, 0.001);
-    assert_delta!(mat2.m44, 4.0, 0.001);
+    assert_delta!(mat2[(0, 0)], 0.25, 0.001);
+    assert_delta!(mat2[(0, 1)], 0.5, 0.001);
+    assert_delta!(mat2[(0, 2)], 0.75, 0.001);
+    assert_delta!(mat2[(0, 3)], 1.0, 0.001);
+    assert_delta!(mat2[(1, 0)], 1.25, 0.001);
+    assert_delta!(mat2[(1, 1)], 1.5, 0.001);
+    assert_delta!(mat2[(1, 2)], 1.75, 0.001);
+    assert_delta!(mat2[(1, 3)], 2.0, 0.001);
+    assert_delta!(mat2[(2, 0)], 2.25, 0.001);
+    assert_delta!(mat2[(2, 1)], 2.5, 0.001);
+    assert_delta!(mat2[(2, 2)], 2.75, 0.001);
+    assert_delta!(mat2[(2, 3)], 3.0, 0.001);
+    assert_delta!(mat2[(3, 0)], 3.25, 0.001);
+    assert_delta!(mat2[(3, 1)], 3.5, 0.001);
+    assert_delta!(mat2[(3, 2)], 3.75, 0.001);
+    assert_delta!(mat2[(3, 3)], 4.0, 0.001);
 }
 
 #[test]
 fn matrix44_can_be_inverted() {
     let mat1: Matrix44<f64> = Matrix44::new(0.707107, 0.0, -0.707107, 0.0, -0.331295, 0.883452, -0.331295, 0.0, 0.624695, 0.468521, 0.624695, 0.0, 4.000574, 3.00043, 4.000574, 1.0);
-    let mat2 = mat1.inverse();
-    assert_delta!(mat2.m11, 0.707107, 0.001);
-    assert_delta!(mat2.m12, -0.331295, 0.001);
-    assert_delta!(mat2.m13, 0.624695, 0.001);
-    assert_delta!(mat2.m14, 0.0, 0.001);
-    assert_delta!(mat2.m21, 0.0, 0.001);
-    assert_delta!(mat2.m22, 0.883452, 0.001);
-    assert_delta!(mat2.m23, 0.468521, 0.001);
-    assert_delta!(mat2.m24, 0.0, 0.001);
-    assert_delta!(mat2.m31, -0.707107, 0.001);
-    assert_delta!(mat2.m32, -0.331295, 0.001);
-    assert_delta!(mat2.m33, 0.624695, 0.001);
-    assert_delta!(mat2.m34, 0.0, 0.001);
-    assert_delta!(mat2.m41, 0.0, 0.001);
-    assert_delta!(mat2.m42, 0.0, 0.001);
-    assert_delta!(mat2.m43, -6.404043, 0.001);
-    assert_delta!(mat2.m44, 1.0, 0.001);
+    let mat2 = mat1.try_inverse().unwrap();
+    assert_delta!(mat2[(0, 0)], 0.707107, 0.001);
+    assert_delta!(mat2[(0, 1)], -0.331295, 0.001);
+    assert_delta!(mat2[(0, 2)], 0.624695, 0.001);
+    assert_delta!(mat2[(0, 3)], 0.0, 0.001);
+    assert_delta!(mat2[(1, 0)], 0.0, 0.001);
+    assert_delta!(mat2[(1, 1)], 0.883452, 0.001);
+    assert_delta!(mat2[(1, 2)], 0.468521, 0.001);
+    assert_delta!(mat2[(1, 3)], 0.0, 0.001);
+    assert_delta!(mat2[(2, 0)], -0.707107, 0.001);
+    assert_delta!(mat2[(2, 1)], -0.331295, 0.001);
+    assert_delta!(mat2[(2, 2)], 0.624695, 0.001);
+    assert_delta!(mat2[(2, 3)], 0.0, 0.001);
+    assert_delta!(mat2[(3, 0)], 0.0, 0.001);
+    assert_delta!(mat2[(3, 1)], 0.0, 0.001);
+    assert_delta!(mat2[(3, 2)], -6.404043, 0.001);
+    assert_delta!(mat2[(3, 3)], 1.0, 0.001);
 }
 
 #[test]
-fn matrix44_can_be_inverted2() {
-    let mat1: Matrix44<f64> = Matrix44::new(0.707107, 0.0, -0.707107, 0.0, -0.331295, 0.883452, -0.331295, 0.0, 0.624695, 0.468521, 0.624695, 0.0, 4.000574, 3.00043, 4.000574, 1.0);
-    let mat2 = mat1.inverse2();
-    assert_delta!(mat2.m11, 0.707107, 0.001);
-    assert_delta!(mat2.m12, -0.331295, 0.001);
-    assert_delta!(mat2.m13, 0.624695, 0.001);
-    assert_delta!(mat2.m14, 0.0, 0.001);
-    assert_delta!(mat2.m21, 0.0, 0.001);
-    assert_delta!(mat2.m22, 0.883452, 0.001);
-    assert_delta!(mat2.m23, 0.468521, 0.001);
-    assert_delta!(mat2.m24, 0.0, 0.001);
-    assert_delta!(mat2.m31, -0.707107, 0.001);
-    assert_delta!(mat2.m32, -0.331295, 0.001);
-    assert_delta!(mat2.m33, 0.624695, 0.001);
-    assert_delta!(mat2.m34, 0.0, 0.001);
-    assert_delta!(mat2.m41, 0.0, 0.001);
-    assert_delta!(mat2.m42, 0.0, 0.001);
-    assert_delta!(mat2.m43, -6.404043, 0.001);
-    assert_delta!(mat2.m44, 1.0, 0.001);
+fn matrix44_can_be_inverted_with_pivoting() {
+    /* Zeros on the diagonal need rows to be swapped. */
+    let mat1: Matrix44<f64> = Matrix44::new(0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 4.0, 0.0, 0.5, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 1.0);
+    let mat2 = mat1.try_inverse().unwrap();
+    let expected: Matrix44<f64> = Matrix44::new(0.0, 0.0, 2.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.25, 0.0, 0.0, -1.0, -0.75, -2.0, 1.0);
+    assert_matrix_delta(&mat2, &expected, 0.0001);
+    assert_matrix_delta(&(mat1 * mat2), &Matrix44::new_identity(), 0.0001);
+}
+
+#[test]
+fn matrix44_should_not_invert_singular_matrices() {
+    let mat1: Matrix44<f64> = Matrix44::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0);
+    assert!(mat1.try_inverse().is_none());
+    assert!(Matrix44::<f64>::new_scaling(1.0, 0.0, 1.0).try_inverse().is_none());
+    assert!(Matrix44::<f64>::new_scaling(1.0e-20, 1.0, 1.0).try_inverse().is_none());
+    assert!(Matrix44::<f64>::new_translation(f64::NAN, 0.0, 0.0).try_inverse().is_none());
+
+    /* Uniformly tiny matrices are still invertible. */
+    assert!(Matrix44::<f64>::new_identity().scale(1.0e-20).try_inverse().is_some());
+}
+
+#[test]
+fn matrix44_has_a_determinant() {
+    let mat1: Matrix44<f64> = Matrix44::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0);
+    assert_delta!(mat1.determinant(), 0.0, 0.0001);
+    assert_delta!(Matrix44::<f64>::new_identity().determinant(), 1.0, 0.0001);
+    assert_delta!(Matrix44::<f64>::new_scaling(2.0, 3.0, -4.0).determinant(), -24.0, 0.0001);
+    assert_delta!(Matrix44::<f64>::new_rotation_y(0.3).determinant(), 1.0, 0.0001);
+
+    let mat2: Matrix44<f64> = Matrix44::new(0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 4.0, 0.0, 0.5, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 1.0);
+    assert_delta!(mat2.determinant(), 4.0, 0.0001);
+}
+
+#[test]
+fn matrix44_can_be_created_as_translation() {
+    let mat1: Matrix44<f64> = Matrix44::new_translation(1.0, -2.0, 3.0);
+    let expected: Matrix44<f64> = Matrix44::new(1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, -2.0, 3.0, 1.0);
+    assert_matrix_delta(&mat1, &expected, 0.0001);
+
+    let point = mat1.multVecMatrix(&Vec3::new(1.0, 1.0, 1.0));
+    assert_delta!(point.x, 2.0, 0.0001);
+    assert_delta!(point.y, -1.0, 0.0001);
+    assert_delta!(point.z, 4.0, 0.0001);
+
+    let direction = mat1.multDirMatrix(&Vec3::new(1.0, 1.0, 1.0));
+    assert_delta!(direction.x, 1.0, 0.0001);
+    assert_delta!(direction.y, 1.0, 0.0001);
+    assert_delta!(direction.z, 1.0, 0.0001);
+}
+
+#[test]
+fn matrix44_can_be_created_as_scaling() {
+    let mat1: Matrix44<f64> = Matrix44::new_scaling(2.0, 3.0, -4.0);
+    let expected: Matrix44<f64> = Matrix44::new(2.0, 0.0, 0.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0, 0.0, -4.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+    assert_matrix_delta(&mat1, &expected, 0.0001);
+}
+
+#[test]
+fn matrix44_can_be_created_as_rotation_about_x_y_and_z() {
+    let angle = 30.0_f64.to_radians();
+    let (s, c) = (0.5, 0.8660254);
+
+    let rx: Matrix44<f64> = Matrix44::new_rotation_x(angle);
+    assert_matrix_delta(&rx, &Matrix44::new(1.0, 0.0, 0.0, 0.0, 0.0, c, s, 0.0, 0.0, -s, c, 0.0, 0.0, 0.0, 0.0, 1.0), 0.0001);
+
+    let ry: Matrix44<f64> = Matrix44::new_rotation_y(angle);
+    assert_matrix_delta(&ry, &Matrix44::new(c, 0.0, -s, 0.0, 0.0, 1.0, 0.0, 0.0, s, 0.0, c, 0.0, 0.0, 0.0, 0.0, 1.0), 0.0001);
+
+    let rz: Matrix44<f64> = Matrix44::new_rotation_z(angle);
+    assert_matrix_delta(&rz, &Matrix44::new(c, s, 0.0, 0.0, -s, c, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0), 0.0001);
+
+    /* Quarter turns take each axis onto the next one. */
+    let quarter = 90.0_f64.to_radians();
+    let y = Matrix44::new_rotation_x(quarter).multVecMatrix(&Vec3::new(0.0, 1.0, 0.0));
+    assert_delta!(y.z, 1.0, 0.0001);
+    let z = Matrix44::new_rotation_y(quarter).multVecMatrix(&Vec3::new(0.0, 0.0, 1.0));
+    assert_delta!(z.x, 1.0, 0.0001);
+    let x = Matrix44::new_rotation_z(quarter).multVecMatrix(&Vec3::new(1.0, 0.0, 0.0));
+    assert_delta!(x.y, 1.0, 0.0001);
+}
+
+#[test]
+fn matrix44_can_be_created_as_rotation_about_an_axis() {
+    let angle = 0.7;
+    assert_matrix_delta(&Matrix44::new_rotation(&Vec3::new(2.0, 0.0, 0.0), angle), &Matrix44::new_rotation_x(angle), 0.0001);
+    assert_matrix_delta(&Matrix44::new_rotation(&Vec3::new(0.0, 3.0, 0.0), angle), &Matrix44::new_rotation_y(angle), 0.0001);
+    assert_matrix_delta(&Matrix44::new_rotation(&Vec3::new(0.0, 0.0, 1.0), angle), &Matrix44::new_rotation_z(angle), 0.0001);
+
+    /* A third of a turn about (1, 1, 1) cycles x -> y -> z -> x. */
+    let mat1: Matrix44<f64> = Matrix44::new_rotation(&Vec3::new(1.0, 1.0, 1.0), 120.0_f64.to_radians());
+    let expected: Matrix44<f64> = Matrix44::new(0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+    assert_matrix_delta(&mat1, &expected, 0.0001);
+}
+
+#[test]
+fn matrix44_transforms_compose_left_to_right() {
+    let mat1: Matrix44<f64> = Matrix44::new_rotation_z(90.0_f64.to_radians()) * Matrix44::new_translation(1.0, 0.0, 0.0);
+    let point = mat1.multVecMatrix(&Vec3::new(1.0, 0.0, 0.0));
+    assert_delta!(point.x, 1.0, 0.0001);
+    assert_delta!(point.y, 1.0, 0.0001);
+    assert_delta!(point.z, 0.0, 0.0001);
+}
+
+#[test]
+fn matrix44_can_be_created_as_look_at() {
+    let mat1: Matrix44<f64> = Matrix44::new_look_at(&Vec3::new(0.0, 0.0, 5.0), &Vec3::new(0.0, 0.0, 0.0), &Vec3::new(0.0, 1.0, 0.0));
+    assert_matrix_delta(&mat1, &Matrix44::new_translation(0.0, 0.0, 5.0), 0.0001);
+
+    let mat2: Matrix44<f64> = Matrix44::new_look_at(&Vec3::new(1.0, 2.0, 3.0), &Vec3::new(1.0, 2.0, -3.0), &Vec3::new(0.0, 1.0, 0.0));
+    assert_matrix_delta(&mat2, &Matrix44::new_translation(1.0, 2.0, 3.0), 0.0001);
+
+    /* Looking down the x axis from the right of the origin. */
+    let mat3: Matrix44<f64> = Matrix44::new_look_at(&Vec3::new(4.0, 0.0, 0.0), &Vec3::new(0.0, 0.0, 0.0), &Vec3::new(0.0, 1.0, 0.0));
+    let expected: Matrix44<f64> = Matrix44::new(0.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 4.0, 0.0, 0.0, 1.0);
+    assert_matrix_delta(&mat3, &expected, 0.0001);
+
+    /* The target ends up straight ahead of the camera. */
+    let target = mat3.try_inverse().unwrap().multVecMatrix(&Vec3::new(0.0, 0.0, 0.0));
+    assert_delta!(target.x, 0.0, 0.0001);
+    assert_delta!(target.y, 0.0, 0.0001);
+    assert_delta!(target.z, -4.0, 0.0001);
+}
+
+#[test]
+fn matrix44_can_be_created_as_frustum() {
+    let mat1: Matrix44<f64> = Matrix44::new_frustum(-1.0, 3.0, -2.0, 2.0, 1.0, 11.0);
+    let expected: Matrix44<f64> = Matrix44::new(0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.5, 0.0, -1.2, -1.0, 0.0, 0.0, -2.2, 0.0);
+    assert_matrix_delta(&mat1, &expected, 0.0001);
+
+    /* Corners of the near plane land on the corners of the NDC cube. */
+    let near = mat1.multVecMatrix(&Vec3::new(3.0, -2.0, -1.0));
+    assert_delta!(near.x, 1.0, 0.0001);
+    assert_delta!(near.y, -1.0, 0.0001);
+    assert_delta!(near.z, -1.0, 0.0001);
+
+    let far = mat1.multVecMatrix(&Vec3::new(-11.0, 22.0, -11.0));
+    assert_delta!(far.x, -1.0, 0.0001);
+    assert_delta!(far.y, 1.0, 0.0001);
+    assert_delta!(far.z, 1.0, 0.0001);
+}
+
+#[test]
+fn matrix44_can_be_created_as_perspective() {
+    let mat1: Matrix44<f64> = Matrix44::new_perspective(90.0_f64.to_radians(), 2.0, 0.5, 100.0);
+    let expected: Matrix44<f64> = Matrix44::new_frustum(-1.0, 1.0, -0.5, 0.5, 0.5, 100.0);
+    assert_matrix_delta(&mat1, &expected, 0.0001);
+
+    let point = mat1.multVecMatrix(&Vec3::new(2.0, 1.0, -2.0));
+    assert_delta!(point.x, 0.5, 0.0001);
+    assert_delta!(point.y, 0.5, 0.0001);
+}
+
+#[test]
+fn matrix44_can_be_created_as_orthographic() {
+    let mat1: Matrix44<f64> = Matrix44::new_orthographic(0.0, 4.0, -1.0, 1.0, 1.0, 3.0);
+    let expected: Matrix44<f64> = Matrix44::new(0.5, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, -1.0, 0.0, -2.0, 1.0);
+    assert_matrix_delta(&mat1, &expected, 0.0001);
+
+    let point = mat1.multVecMatrix(&Vec3::new(3.0, 0.5, -3.0));
+    assert_delta!(point.x, 0.5, 0.0001);
+    assert_delta!(point.y, 0.5, 0.0001);
+    assert_delta!(point.z, 1.0, 0.0001);
 }
 
 #[test]
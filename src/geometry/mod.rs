@@ -1,2 +1,6 @@
 pub mod vector;
-pub mod matrix;
\ No newline at end of file
+pub mod matrix;
+pub mod quaternion;
+pub mod bounds;
+pub mod intersection;
+pub mod bvh;
\ No newline at end of file
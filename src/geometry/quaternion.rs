@@ -0,0 +1,284 @@
+extern crate num;
+use std::ops::Mul;
+
+use geometry::matrix::*;
+use geometry::vector::*;
+
+/* A rotation stored as the quaternion w + xi + yj + zk. Rotations built
+   here are unit quaternions; combining many of them still accumulates
+   floating point error, so normalize now and then. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quat<T: num::Float> {
+    pub w: T,
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: num::Float> Quat<T> {
+    pub fn new(w: T, x: T, y: T, z: T) -> Quat<T> {
+        Quat{w, x, y, z}
+    }
+
+    pub fn new_identity() -> Quat<T> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+
+        Quat::new(one, zero, zero, zero)
+    }
+
+    /* Rotation by angle radians about axis, counter-clockwise when looking
+       down the axis towards the origin, like Matrix44::new_rotation. The
+       axis does not need to be normalized. */
+    pub fn new_axis_angle(axis: &Vec3<T>, angle: T) -> Quat<T> {
+        let two: T = num::cast::<f64, T>(2.0).unwrap();
+        let u = axis.normalize();
+        let (sin, cos) = ((angle / two).sin(), (angle / two).cos());
+
+        Quat::new(cos, u.x * sin, u.y * sin, u.z * sin)
+    }
+
+    /* Rotation about x, then y, then z, in radians. The same rotation as
+       Matrix44::new_rotation_x(x) * Matrix44::new_rotation_y(y) *
+       Matrix44::new_rotation_z(z). */
+    pub fn new_euler(x: T, y: T, z: T) -> Quat<T> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+        let qx = Quat::new_axis_angle(&Vec3::new(one, zero, zero), x);
+        let qy = Quat::new_axis_angle(&Vec3::new(zero, one, zero), y);
+        let qz = Quat::new_axis_angle(&Vec3::new(zero, zero, one), z);
+
+        qz * qy * qx
+    }
+
+    pub fn dot(&self, other: &Quat<T>) -> T {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn length(&self) -> T {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Quat<T> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let length = self.length();
+        if length > zero {
+            Quat::new(self.w / length, self.x / length, self.y / length, self.z / length)
+        } else {
+            Quat::new_identity()
+        }
+    }
+
+    /* For a unit quaternion, the conjugate is the opposite rotation. */
+    pub fn conjugate(self) -> Quat<T> {
+        Quat::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn rotate(&self, v: &Vec3<T>) -> Vec3<T> {
+        /* v + 2w(u x v) + 2u x (u x v), with u the vector part. */
+        let two: T = num::cast::<f64, T>(2.0).unwrap();
+        let u = Vec3::new(self.x, self.y, self.z);
+        let t = u.cross_product(*v) * two;
+
+        *v + t * self.w + u.cross_product(t)
+    }
+
+    /* Spherical linear interpolation from self (t = 0) to other (t = 1) at
+       constant angular speed, along the shorter of the two arcs. Both
+       quaternions should be normalized. */
+    pub fn slerp(&self, other: &Quat<T>, t: T) -> Quat<T> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+        let nearly_parallel: T = num::cast::<f64, T>(0.9995).unwrap();
+
+        let mut cos = self.dot(other);
+        let mut other = *other;
+        if cos < zero {
+            cos = -cos;
+            other = Quat::new(-other.w, -other.x, -other.y, -other.z);
+        }
+
+        /* Close quaternions make sin(theta) tiny, so interpolate linearly. */
+        let (a, b) = if cos > nearly_parallel {
+            (one - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((one - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+
+        Quat::new(self.w * a + other.w * b, self.x * a + other.x * b, self.y * a + other.y * b, self.z * a + other.z * b).normalize()
+    }
+}
+
+/* The Hamilton product: a * b rotates by b first, then by a. Note that this
+   is the reverse of Matrix44, so Matrix44::from(a * b) equals
+   Matrix44::from(b) * Matrix44::from(a). */
+impl<T: num::Float> Mul for Quat<T> {
+    type Output = Quat<T>;
+
+    fn mul(self, other: Quat<T>) -> Quat<T> {
+        Quat::new(self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+                  self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+                  self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+                  self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w)
+    }
+}
+
+impl<T: num::Float> From<Quat<T>> for Matrix44<T> {
+    fn from(q: Quat<T>) -> Matrix44<T> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+        let two: T = num::cast::<f64, T>(2.0).unwrap();
+        let q = q.normalize();
+        let (w, x, y, z) = (q.w, q.x, q.y, q.z);
+
+        Matrix44::new(one - two * (y * y + z * z), two * (x * y + w * z), two * (x * z - w * y), zero,
+                      two * (x * y - w * z), one - two * (x * x + z * z), two * (y * z + w * x), zero,
+                      two * (x * z + w * y), two * (y * z - w * x), one - two * (x * x + y * y), zero,
+                      zero, zero, zero, one)
+    }
+}
+
+/* Takes the rotation from the upper 3x3 of the matrix, which must not be
+   scaled or sheared. Translation is ignored. */
+impl<T: num::Float> From<Matrix44<T>> for Quat<T> {
+    fn from(m: Matrix44<T>) -> Quat<T> {
+        let zero: T = num::cast::<f64, T>(0.0).unwrap();
+        let one: T = num::cast::<f64, T>(1.0).unwrap();
+        let two: T = num::cast::<f64, T>(2.0).unwrap();
+        let four: T = num::cast::<f64, T>(4.0).unwrap();
+        let (m11, m22, m33) = (m[(0, 0)], m[(1, 1)], m[(2, 2)]);
+        let trace = m11 + m22 + m33;
+
+        /* Divide by the largest of w, x, y and z to stay accurate. */
+        let q = if trace > zero {
+            let s = (trace + one).sqrt() * two;
+            Quat::new(s / four, (m[(1, 2)] - m[(2, 1)]) / s, (m[(2, 0)] - m[(0, 2)]) / s, (m[(0, 1)] - m[(1, 0)]) / s)
+        } else if m11 > m22 && m11 > m33 {
+            let s = (one + m11 - m22 - m33).sqrt() * two;
+            Quat::new((m[(1, 2)] - m[(2, 1)]) / s, s / four, (m[(1, 0)] + m[(0, 1)]) / s, (m[(2, 0)] + m[(0, 2)]) / s)
+        } else if m22 > m33 {
+            let s = (one + m22 - m11 - m33).sqrt() * two;
+            Quat::new((m[(2, 0)] - m[(0, 2)]) / s, (m[(0, 1)] + m[(1, 0)]) / s, s / four, (m[(2, 1)] + m[(1, 2)]) / s)
+        } else {
+            let s = (one + m33 - m11 - m22).sqrt() * two;
+            Quat::new((m[(0, 1)] - m[(1, 0)]) / s, (m[(2, 0)] + m[(0, 2)]) / s, (m[(2, 1)] + m[(1, 2)]) / s, s / four)
+        };
+
+        q.normalize()
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+fn assert_same_rotation(a: &Matrix44<f64>, b: &Matrix44<f64>) {
+    for i in 0..4 {
+        for j in 0..4 {
+            assert_delta!(a[(i, j)], b[(i, j)], 0.0001);
+        }
+    }
+}
+
+#[test]
+fn quat_can_be_created_from_axis_angle() {
+    let q: Quat<f64> = Quat::new_axis_angle(&Vec3::new(0.0, 0.0, 2.0), 90.0_f64.to_radians());
+    assert_delta!(q.w, ::std::f64::consts::FRAC_1_SQRT_2, 0.0001);
+    assert_delta!(q.x, 0.0, 0.0001);
+    assert_delta!(q.y, 0.0, 0.0001);
+    assert_delta!(q.z, ::std::f64::consts::FRAC_1_SQRT_2, 0.0001);
+    assert_delta!(q.length(), 1.0, 0.0001);
+
+    let v = q.rotate(&Vec3::new(1.0, 0.0, 0.0));
+    assert_delta!(v.x, 0.0, 0.0001);
+    assert_delta!(v.y, 1.0, 0.0001);
+    assert_delta!(v.z, 0.0, 0.0001);
+}
+
+#[test]
+fn quat_matches_matrix_rotations() {
+    let axis = Vec3::new(1.0, -2.0, 0.5);
+    let q: Quat<f64> = Quat::new_axis_angle(&axis, 1.2);
+    assert_same_rotation(&Matrix44::from(q), &Matrix44::new_rotation(&axis, 1.2));
+
+    let p = Vec3::new(0.3, 4.0, -1.5);
+    let by_quat = q.rotate(&p);
+    let by_matrix = Matrix44::new_rotation(&axis, 1.2).multVecMatrix(&p);
+    assert_delta!(by_quat.x, by_matrix.x, 0.0001);
+    assert_delta!(by_quat.y, by_matrix.y, 0.0001);
+    assert_delta!(by_quat.z, by_matrix.z, 0.0001);
+}
+
+#[test]
+fn quat_can_be_created_from_euler_angles() {
+    let (x, y, z) = (0.4, -1.1, 2.5);
+    let q: Quat<f64> = Quat::new_euler(x, y, z);
+    let expected = Matrix44::new_rotation_x(x) * Matrix44::new_rotation_y(y) * Matrix44::new_rotation_z(z);
+    assert_same_rotation(&Matrix44::from(q), &expected);
+}
+
+#[test]
+fn quat_can_be_multiplied() {
+    let a: Quat<f64> = Quat::new_axis_angle(&Vec3::new(1.0, 0.0, 0.0), 0.5);
+    let b: Quat<f64> = Quat::new_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 0.7);
+    assert_same_rotation(&Matrix44::from(a * b), &(Matrix44::from(b) * Matrix44::from(a)));
+
+    /* Multiplying by the conjugate undoes the rotation. */
+    let identity = a * a.conjugate();
+    assert_delta!(identity.dot(&Quat::new_identity()), 1.0, 0.0001);
+}
+
+#[test]
+fn quat_can_be_normalized() {
+    let q: Quat<f64> = Quat::new(2.0, 0.0, 0.0, 2.0).normalize();
+    assert_delta!(q.w, ::std::f64::consts::FRAC_1_SQRT_2, 0.0001);
+    assert_delta!(q.z, ::std::f64::consts::FRAC_1_SQRT_2, 0.0001);
+    assert_eq!(Quat::new_identity(), Quat::new(0.0, 0.0, 0.0, 0.0).normalize());
+}
+
+#[test]
+fn quat_can_be_converted_from_matrix() {
+    /* Exercise every branch: small and large rotations about each axis. */
+    let rotations: Vec<Quat<f64>> = vec![
+        Quat::new_axis_angle(&Vec3::new(1.0, 2.0, 3.0), 0.3),
+        Quat::new_axis_angle(&Vec3::new(1.0, 0.1, 0.0), 3.0),
+        Quat::new_axis_angle(&Vec3::new(0.1, 1.0, 0.0), 3.0),
+        Quat::new_axis_angle(&Vec3::new(0.0, 0.1, 1.0), 3.0),
+    ];
+    for q in rotations {
+        let back = Quat::from(Matrix44::from(q));
+        /* q and -q are the same rotation. */
+        assert_delta!(back.dot(&q).abs(), 1.0, 0.0001);
+    }
+
+    let with_translation = Matrix44::new_rotation_y(0.8) * Matrix44::new_translation(5.0, 6.0, 7.0);
+    let q: Quat<f64> = Quat::from(with_translation);
+    assert_same_rotation(&Matrix44::from(q), &Matrix44::new_rotation_y(0.8));
+}
+
+#[test]
+fn quat_can_slerp() {
+    let a: Quat<f64> = Quat::new_identity();
+    let b: Quat<f64> = Quat::new_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 90.0_f64.to_radians());
+
+    assert_delta!(a.slerp(&b, 0.0).dot(&a), 1.0, 0.0001);
+    assert_delta!(a.slerp(&b, 1.0).dot(&b), 1.0, 0.0001);
+
+    /* Halfway is a 45 degree turn about the same axis. */
+    let half = a.slerp(&b, 0.5);
+    let expected = Quat::new_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 45.0_f64.to_radians());
+    assert_delta!(half.dot(&expected), 1.0, 0.0001);
+
+    /* The shorter arc is taken even when the signs differ. */
+    let negated = Quat::new(-b.w, -b.x, -b.y, -b.z);
+    assert_delta!(a.slerp(&negated, 0.5).dot(&expected).abs(), 1.0, 0.0001);
+
+    /* Nearly equal quaternions don't divide by zero. */
+    let c = Quat::new_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 1.0e-6);
+    let between = a.slerp(&c, 0.5);
+    assert!(between.w.is_finite());
+    assert_delta!(between.length(), 1.0, 0.0001);
+}
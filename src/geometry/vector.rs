@@ -1,9 +1,9 @@
 extern crate num;
-use std::ops::{Add, Sub};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
 
 use geometry::matrix::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Vec3<T: num::Float> {
     pub x: T,
     pub y: T,
@@ -15,28 +15,34 @@ impl<T: num::Float> Vec3<T> {
         Vec3{x: x, y: y, z: z}
     }
 
-    fn scale(self, other: T) -> Vec3<T> {
+    pub fn scale(self, other: T) -> Vec3<T> {
         Vec3{x: self.x * other, y: self.y * other, z: self.z * other}
     }
 
-    fn dot_product(self, other: Vec3<T>) -> T {
+    pub fn dot_product(self, other: Vec3<T>) -> T {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
-    fn cross_product(self, other: Vec3<T>) -> Vec3<T> {
+    pub fn cross_product(self, other: Vec3<T>) -> Vec3<T> {
         Vec3{x: self.y * other.z - self.z * other.y, y: self.z * other.x - self.x * other.z, z: self.x * other.y - self.y * other.x}
     }
 
-    fn norm(&self) -> T {
+    /* Cheaper than length when only comparing lengths. */
+    pub fn length_squared(&self) -> T {
         self.x * self.x + self.y * self.y + self.z * self.z
     }
 
-    fn length(&self) -> T {
-        self.norm().sqrt()
+    pub fn length(&self) -> T {
+        self.length_squared().sqrt()
     }
 
-    fn normalize(self) -> Vec3<T>{
-        let n = self.norm();
+    pub fn distance(self, other: Vec3<T>) -> T {
+        (other - self).length()
+    }
+
+    /* The zero vector is returned unchanged. */
+    pub fn normalize(self) -> Vec3<T>{
+        let n = self.length_squared();
         let mut x = self.x;
         let mut y = self.y;
         let mut z = self.z;
@@ -48,6 +54,20 @@ impl<T: num::Float> Vec3<T> {
         }
         Vec3{x: x, y: y, z: z}
     }
+
+    /* Component-wise minimum and maximum, for bounding boxes. */
+    pub fn min(self, other: Vec3<T>) -> Vec3<T> {
+        Vec3{x: self.x.min(other.x), y: self.y.min(other.y), z: self.z.min(other.z)}
+    }
+
+    pub fn max(self, other: Vec3<T>) -> Vec3<T> {
+        Vec3{x: self.x.max(other.x), y: self.y.max(other.y), z: self.z.max(other.z)}
+    }
+
+    /* Linear interpolation: self at t = 0, other at t = 1. */
+    pub fn lerp(self, other: Vec3<T>, t: T) -> Vec3<T> {
+        self + (other - self) * t
+    }
 }
 
 impl<T: num::Float> Add for Vec3<T> {
@@ -66,16 +86,286 @@ impl<T: num::Float> Sub for Vec3<T> {
     }
 }
 
+impl<T: num::Float> Neg for Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn neg(self) -> Vec3<T> {
+        Vec3{x: -self.x, y: -self.y, z: -self.z}
+    }
+}
+
+impl<T: num::Float> Mul<T> for Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn mul(self, other: T) -> Vec3<T> {
+        self.scale(other)
+    }
+}
+
+impl<T: num::Float> Div<T> for Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn div(self, other: T) -> Vec3<T> {
+        Vec3{x: self.x / other, y: self.y / other, z: self.z / other}
+    }
+}
+
+impl<T: num::Float> AddAssign for Vec3<T> {
+    fn add_assign(&mut self, other: Vec3<T>) {
+        *self = *self + other;
+    }
+}
+
+impl<T: num::Float> SubAssign for Vec3<T> {
+    fn sub_assign(&mut self, other: Vec3<T>) {
+        *self = *self - other;
+    }
+}
+
+/* By reference versions of the operators, so callers holding references
+   don't need to dereference first. */
+
+impl<'a, T: num::Float> Add for &'a Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn add(self, other: &'a Vec3<T>) -> Vec3<T> {
+        *self + *other
+    }
+}
+
+impl<'a, T: num::Float> Sub for &'a Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn sub(self, other: &'a Vec3<T>) -> Vec3<T> {
+        *self - *other
+    }
+}
+
+impl<T: num::Float> Neg for &Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn neg(self) -> Vec3<T> {
+        -*self
+    }
+}
+
+impl<T: num::Float> Mul<T> for &Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn mul(self, other: T) -> Vec3<T> {
+        *self * other
+    }
+}
+
+impl<T: num::Float> Div<T> for &Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn div(self, other: T) -> Vec3<T> {
+        *self / other
+    }
+}
+
+impl<'a, T: num::Float> AddAssign<&'a Vec3<T>> for Vec3<T> {
+    fn add_assign(&mut self, other: &'a Vec3<T>) {
+        *self = *self + *other;
+    }
+}
+
+impl<'a, T: num::Float> SubAssign<&'a Vec3<T>> for Vec3<T> {
+    fn sub_assign(&mut self, other: &'a Vec3<T>) {
+        *self = *self - *other;
+    }
+}
+
+
+
+
+
+/* Raster positions and texture coordinates. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vec2<T: num::Float> {
+    pub x: T,
+    pub y: T,
+}
 
+impl<T: num::Float> Vec2<T> {
+    pub fn new(x: T, y: T) -> Vec2<T> {
+        Vec2{x, y}
+    }
 
+    pub fn dot_product(self, other: Vec2<T>) -> T {
+        self.x * other.x + self.y * other.y
+    }
 
+    /* The z component of the 3D cross product, positive when other is
+       counter-clockwise from self. */
+    pub fn perp_dot_product(self, other: Vec2<T>) -> T {
+        self.x * other.y - self.y * other.x
+    }
 
-macro_rules! assert_delta {
-    ($x:expr, $y:expr, $d:expr) => {
-        if $x - $y >= $d || $y - $x >= $d { panic!(); }
+    pub fn length_squared(&self) -> T {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn length(&self) -> T {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance(self, other: Vec2<T>) -> T {
+        (other - self).length()
+    }
+
+    /* The zero vector is returned unchanged. */
+    pub fn normalize(self) -> Vec2<T> {
+        let length = self.length();
+        if length > num::cast::<f64, T>(0.0).unwrap() {
+            self / length
+        } else {
+            self
+        }
+    }
+
+    pub fn min(self, other: Vec2<T>) -> Vec2<T> {
+        Vec2{x: self.x.min(other.x), y: self.y.min(other.y)}
+    }
+
+    pub fn max(self, other: Vec2<T>) -> Vec2<T> {
+        Vec2{x: self.x.max(other.x), y: self.y.max(other.y)}
+    }
+
+    pub fn lerp(self, other: Vec2<T>, t: T) -> Vec2<T> {
+        self + (other - self) * t
+    }
+}
+
+impl<T: num::Float> Add for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn add(self, other: Vec2<T>) -> Vec2<T> {
+        Vec2{x: self.x + other.x, y: self.y + other.y}
     }
 }
 
+impl<T: num::Float> Sub for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn sub(self, other: Vec2<T>) -> Vec2<T> {
+        Vec2{x: self.x - other.x, y: self.y - other.y}
+    }
+}
+
+impl<T: num::Float> Neg for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn neg(self) -> Vec2<T> {
+        Vec2{x: -self.x, y: -self.y}
+    }
+}
+
+impl<T: num::Float> Mul<T> for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn mul(self, other: T) -> Vec2<T> {
+        Vec2{x: self.x * other, y: self.y * other}
+    }
+}
+
+impl<T: num::Float> Div<T> for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn div(self, other: T) -> Vec2<T> {
+        Vec2{x: self.x / other, y: self.y / other}
+    }
+}
+
+/* A homogeneous point (w = 1) or direction (w = 0). Transforming one with
+   Matrix44 keeps w, so clip space coordinates are available before the
+   perspective divide. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vec4<T: num::Float> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
+impl<T: num::Float> Vec4<T> {
+    pub fn new(x: T, y: T, z: T, w: T) -> Vec4<T> {
+        Vec4{x, y, z, w}
+    }
+
+    pub fn from_point(p: Vec3<T>) -> Vec4<T> {
+        Vec4{x: p.x, y: p.y, z: p.z, w: num::cast::<f64, T>(1.0).unwrap()}
+    }
+
+    pub fn from_direction(d: Vec3<T>) -> Vec4<T> {
+        Vec4{x: d.x, y: d.y, z: d.z, w: num::cast::<f64, T>(0.0).unwrap()}
+    }
+
+    /* Drops w without dividing by it. */
+    pub fn xyz(self) -> Vec3<T> {
+        Vec3::new(self.x, self.y, self.z)
+    }
+
+    /* Divides by w, taking a point from clip space to NDC. */
+    pub fn perspective_divide(self) -> Vec3<T> {
+        Vec3::new(self.x / self.w, self.y / self.w, self.z / self.w)
+    }
+
+    pub fn dot_product(self, other: Vec4<T>) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+}
+
+impl<T: num::Float> Add for Vec4<T> {
+    type Output = Vec4<T>;
+
+    fn add(self, other: Vec4<T>) -> Vec4<T> {
+        Vec4{x: self.x + other.x, y: self.y + other.y, z: self.z + other.z, w: self.w + other.w}
+    }
+}
+
+impl<T: num::Float> Sub for Vec4<T> {
+    type Output = Vec4<T>;
+
+    fn sub(self, other: Vec4<T>) -> Vec4<T> {
+        Vec4{x: self.x - other.x, y: self.y - other.y, z: self.z - other.z, w: self.w - other.w}
+    }
+}
+
+impl<T: num::Float> Neg for Vec4<T> {
+    type Output = Vec4<T>;
+
+    fn neg(self) -> Vec4<T> {
+        Vec4{x: -self.x, y: -self.y, z: -self.z, w: -self.w}
+    }
+}
+
+impl<T: num::Float> Mul<T> for Vec4<T> {
+    type Output = Vec4<T>;
+
+    fn mul(self, other: T) -> Vec4<T> {
+        Vec4{x: self.x * other, y: self.y * other, z: self.z * other, w: self.w * other}
+    }
+}
+
+/* Transforms v as a row vector, v * M, like multVecMatrix but without the
+   divide by w. */
+impl<T: num::Float> Mul<Vec4<T>> for Matrix44<T> {
+    type Output = Vec4<T>;
+
+    fn mul(self, v: Vec4<T>) -> Vec4<T> {
+        Vec4{x: v.x * self[(0, 0)] + v.y * self[(1, 0)] + v.z * self[(2, 0)] + v.w * self[(3, 0)],
+             y: v.x * self[(0, 1)] + v.y * self[(1, 1)] + v.z * self[(2, 1)] + v.w * self[(3, 1)],
+             z: v.x * self[(0, 2)] + v.y * self[(1, 2)] + v.z * self[(2, 2)] + v.w * self[(3, 2)],
+             w: v.x * self[(0, 3)] + v.y * self[(1, 3)] + v.z * self[(2, 3)] + v.w * self[(3, 3)]}
+    }
+}
+
+
+
+
+
 fn assert_eq_float_range<T: num::Float>(n1: T, n2: T, range: T) {
     assert!((n1 - n2).abs() < range.abs());
 }
@@ -136,10 +426,10 @@ fn vec3_can_cross_product() {
 }
 
 #[test]
-fn vec3_can_norm() {
+fn vec3_can_length_squared() {
     let vec1: Vec3<f64> = Vec3::new(1.1, 2.2, 3.3);
-    let norm = vec1.norm();
-    assert_delta!(norm, 16.94, 0.001);
+    let length_squared = vec1.length_squared();
+    assert_delta!(length_squared, 16.94, 0.001);
 }
 
 #[test]
@@ -156,4 +446,119 @@ fn vec3_can_normalize() {
     assert_delta!(vec2.x, 0.8018, 0.001);
     assert_delta!(vec2.y, 0.5345, 0.001);
     assert_delta!(vec2.z, -0.2673, 0.001);
-}
\ No newline at end of file
+}
+
+#[test]
+fn vec3_can_normalize_zero() {
+    let vec1: Vec3<f64> = Vec3::new(0.0, 0.0, 0.0);
+    assert_eq!(vec1, vec1.normalize());
+}
+
+#[test]
+fn vec3_can_negate() {
+    let vec1: Vec3<f64> = Vec3::new(1.0, -2.0, 3.0);
+    assert_eq!(Vec3::new(-1.0, 2.0, -3.0), -vec1);
+    assert_eq!(Vec3::new(-1.0, 2.0, -3.0), -&vec1);
+}
+
+#[test]
+fn vec3_can_multiply_and_divide_by_scalar() {
+    let vec1: Vec3<f64> = Vec3::new(1.0, 2.0, 3.0);
+    assert_eq!(Vec3::new(2.0, 4.0, 6.0), vec1 * 2.0);
+    assert_eq!(Vec3::new(0.5, 1.0, 1.5), vec1 / 2.0);
+    assert_eq!(Vec3::new(2.0, 4.0, 6.0), &vec1 * 2.0);
+    assert_eq!(Vec3::new(0.5, 1.0, 1.5), &vec1 / 2.0);
+}
+
+#[test]
+fn vec3_can_add_and_subtract_by_reference() {
+    let vec1: Vec3<f64> = Vec3::new(4.0, 6.0, 8.0);
+    let vec2: Vec3<f64> = Vec3::new(0.0, 3.0, 6.0);
+    let (ref1, ref2) = (&vec1, &vec2);
+    assert_eq!(Vec3::new(4.0, 9.0, 14.0), ref1 + ref2);
+    assert_eq!(Vec3::new(4.0, 3.0, 2.0), ref1 - ref2);
+
+    /* Both are still usable, and copies. */
+    let vec3 = vec1;
+    assert_eq!(vec1, vec3);
+}
+
+#[test]
+fn vec3_can_add_assign_and_subtract_assign() {
+    let mut vec1: Vec3<f64> = Vec3::new(1.0, 2.0, 3.0);
+    vec1 += Vec3::new(1.0, 1.0, 1.0);
+    assert_eq!(Vec3::new(2.0, 3.0, 4.0), vec1);
+    vec1 -= &Vec3::new(0.5, 0.5, 0.5);
+    assert_eq!(Vec3::new(1.5, 2.5, 3.5), vec1);
+}
+
+#[test]
+fn vec3_can_min_and_max() {
+    let vec1: Vec3<f64> = Vec3::new(1.0, 5.0, -3.0);
+    let vec2: Vec3<f64> = Vec3::new(2.0, -1.0, -3.5);
+    assert_eq!(Vec3::new(1.0, -1.0, -3.5), vec1.min(vec2));
+    assert_eq!(Vec3::new(2.0, 5.0, -3.0), vec1.max(vec2));
+}
+
+#[test]
+fn vec3_can_lerp() {
+    let vec1: Vec3<f64> = Vec3::new(0.0, 10.0, -2.0);
+    let vec2: Vec3<f64> = Vec3::new(4.0, 20.0, 2.0);
+    assert_eq!(vec1, vec1.lerp(vec2, 0.0));
+    assert_eq!(vec2, vec1.lerp(vec2, 1.0));
+    assert_eq!(Vec3::new(1.0, 12.5, -1.0), vec1.lerp(vec2, 0.25));
+}
+
+#[test]
+fn vec3_can_distance() {
+    let vec1: Vec3<f64> = Vec3::new(1.0, 2.0, 3.0);
+    let vec2: Vec3<f64> = Vec3::new(4.0, 6.0, 3.0);
+    assert_delta!(vec1.distance(vec2), 5.0, 0.001);
+    assert_delta!(vec2.distance(vec1), 5.0, 0.001);
+}
+
+#[test]
+fn vec2_can_be_used_for_raster_coordinates() {
+    let vec1: Vec2<f64> = Vec2::new(3.0, 4.0);
+    let vec2: Vec2<f64> = Vec2::new(1.0, -1.0);
+    assert_eq!(Vec2::new(4.0, 3.0), vec1 + vec2);
+    assert_eq!(Vec2::new(2.0, 5.0), vec1 - vec2);
+    assert_eq!(Vec2::new(-3.0, -4.0), -vec1);
+    assert_eq!(Vec2::new(6.0, 8.0), vec1 * 2.0);
+    assert_eq!(Vec2::new(1.5, 2.0), vec1 / 2.0);
+    assert_delta!(vec1.dot_product(vec2), -1.0, 0.001);
+    assert_delta!(vec1.perp_dot_product(vec2), -7.0, 0.001);
+    assert_delta!(vec1.length(), 5.0, 0.001);
+    assert_delta!(vec1.distance(vec2), 5.3852, 0.001);
+    assert_eq!(Vec2::new(0.6, 0.8), vec1.normalize());
+    assert_eq!(Vec2::new(1.0, -1.0), vec1.min(vec2));
+    assert_eq!(Vec2::new(3.0, 4.0), vec1.max(vec2));
+    assert_eq!(Vec2::new(2.0, 1.5), vec1.lerp(vec2, 0.5));
+}
+
+#[test]
+fn vec4_can_be_created_from_points_and_directions() {
+    let vec1: Vec3<f64> = Vec3::new(1.0, 2.0, 3.0);
+    assert_eq!(Vec4::new(1.0, 2.0, 3.0, 1.0), Vec4::from_point(vec1));
+    assert_eq!(Vec4::new(1.0, 2.0, 3.0, 0.0), Vec4::from_direction(vec1));
+    assert_eq!(vec1, Vec4::new(1.0, 2.0, 3.0, 4.0).xyz());
+    assert_eq!(Vec3::new(0.5, 1.0, 1.5), Vec4::new(1.0, 2.0, 3.0, 2.0).perspective_divide());
+}
+
+#[test]
+fn vec4_keeps_w_when_transformed() {
+    let translation: Matrix44<f64> = Matrix44::new_translation(1.0, 2.0, 3.0);
+    assert_eq!(Vec4::new(2.0, 3.0, 4.0, 1.0), translation * Vec4::new(1.0, 1.0, 1.0, 1.0));
+    /* Directions aren't translated. */
+    assert_eq!(Vec4::new(1.0, 1.0, 1.0, 0.0), translation * Vec4::new(1.0, 1.0, 1.0, 0.0));
+
+    /* A perspective projection puts the depth in w. */
+    let projection: Matrix44<f64> = Matrix44::new_frustum(-1.0, 1.0, -1.0, 1.0, 1.0, 100.0);
+    let clip = projection * Vec4::from_point(Vec3::new(1.0, 0.5, -4.0));
+    assert_delta!(clip.w, 4.0, 0.001);
+    let ndc = clip.perspective_divide();
+    let expected = projection.multVecMatrix(&Vec3::new(1.0, 0.5, -4.0));
+    assert_delta!(ndc.x, expected.x, 0.001);
+    assert_delta!(ndc.y, expected.y, 0.001);
+    assert_delta!(ndc.z, expected.z, 0.001);
+}
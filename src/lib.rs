@@ -0,0 +1,29 @@
+//! Sticks renders wireframes of triangle meshes.
+//!
+//! Objects and materials are read with `wavefront::parse_object` and
+//! `wavefront::parse_materials`, projected through a `Camera` by a `Renderer`
+//! and written out by one of the `Backend`s in `output`. For large meshes,
+//! `wavefront::parse_mesh` reads an indexed `mesh::Mesh` instead.
+
+#[macro_use]
+extern crate nom;
+extern crate num;
+#[cfg(unix)]
+extern crate libc;
+
+#[cfg(test)]
+#[macro_use]
+mod testing;
+
+pub mod camera;
+pub mod geometry;
+pub mod mesh;
+pub mod output;
+pub mod render;
+pub mod validate;
+pub mod wavefront;
+
+pub use camera::Camera;
+pub use output::{Backend, Drawing, Line};
+pub use output::svg::Svg;
+pub use render::Renderer;
@@ -1,140 +1,705 @@
-#[macro_use]
-extern crate nom;
 extern crate clap;
+extern crate sticks;
 
-mod geometry;
-mod wavefront;
-
-use geometry::matrix::*;
-use geometry::vector::*;
-use clap::{App, Arg, SubCommand};
-use std::collections::HashSet;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::ffi::OsString;
 use std::io::prelude::*;
 use std::fs::File;
-use std::error::Error;
-use wavefront::object_parser::{parse_object, Object};
+use std::io;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use sticks::{Backend, Camera, Renderer, Svg};
+use sticks::camera::fit_canvas;
+use sticks::geometry::matrix::Matrix44;
+use sticks::mesh::{Mesh, WeldOptions};
+use sticks::output::dxf::{Dxf, Layers};
+use sticks::output::eps::Eps;
+use sticks::output::page::{Page, parse_length};
+use sticks::output::Rgb;
+use sticks::output::pdf::Pdf;
+use sticks::output::terminal::{Charset, Terminal, pixel_size, terminal_size};
+use sticks::output::tikz::Tikz;
+use sticks::validate::{Issue, Report, validate_source};
+use sticks::wavefront::{parse_materials, parse_mesh, parse_mesh_source, write_mesh};
+use sticks::wavefront::material_parser::Material;
+
+const SUBCOMMANDS: [&str; 5] = ["render", "info", "convert", "validate", "help"];
+const HELP_OPTIONS: [&str; 4] = ["-h", "--help", "-V", "--version"];
+/* The options of app() that take a value, which main() has to skip over
+   when it looks for a subcommand. */
+const VALUE_SHORTS: [char; 4] = ['j', 'm', 'o', 'f'];
+const VALUE_OPTIONS: [&str; 16] = [
+    "--object", "--material", "--output", "--format", "--size", "--margin", "--background", "--canvas",
+    "--fov", "--page", "--stroke-width", "--layers", "--figure-width", "--cols", "--rows", "--weld",
+];
 
-fn computePixelCoordinates (
-    pWorld: &Vec3<f64>,
-    worldToCamera: &Matrix44<f64>,
-    canvasWidth: f64,
-    canvasHeight: f64,
-    imageWidth: u32,
-    imageHeight: u32) -> (u32, u32) {
-    
-    /* Take the point in the world coordinate system and and translate
-       it into the camera coordinate system. */
-    let pCamera = worldToCamera.multVecMatrix(pWorld);
+/* Below these, the projection loses so much precision that nothing useful
+   is drawn. */
+const MIN_FIELD_OF_VIEW: f64 = 0.001;
+const MIN_CANVAS_SIDE: f64 = 1e-6;
+
+const EXIT_CODES: &str = "EXIT CODES:
+    0    Success
+    1    validate found errors, or warnings with --strict
+    2    The command line is invalid
+    3    An input file can't be read
+    4    An input file can't be parsed
+    5    The output can't be written";
+
+fn main() {
+    /* Without a subcommand, render like sticks always did. */
+    let mut args: Vec<OsString> = env::args_os().collect();
+    if is_implicit_render(&args) {
+        args.insert(1, OsString::from("render"));
+    }
 
-    /* Assuming the distance between the camera and the image plane is 1,
-       project this point onto the image plane (the screen), (to the
-       screen coordinate system). */
-    let pScreenX = pCamera.x / -pCamera.z;
-    let pScreenY = pCamera.y / -pCamera.z;
+    let matches = app().get_matches_from_safe(args).unwrap_or_else(|why| {
+        if why.use_stderr() {
+            eprintln!("{}", why.message);
+            process::exit(2);
+        }
+        println!("{}", why.message);
+        process::exit(0);
+    });
 
-    /* Convert the point in the screen coordinate system into a NDC coordinate system. */
-    let pNDCX = (pScreenX + canvasWidth * 0.5) / canvasWidth;
-    let pNDCY = (pScreenY + canvasHeight * 0.5) / canvasHeight;
+    let (name, subcommand) = matches.subcommand();
+    let subcommand = subcommand.expect("clap requires a subcommand");
+    let result = match name {
+        "render" => render(subcommand),
+        "info" => info(subcommand),
+        "convert" => convert(subcommand),
+        "validate" => validate(subcommand),
+        _ => unreachable!("clap only accepts the subcommands of app()"),
+    };
 
-    /* Convert the point in the NCD coordinate system into the raster coordinate system. */
-    let pRasterX: u32 = (pNDCX * imageWidth as f64) as u32;
-    let pRasterY: u32 = ((1.0 - pNDCY) * imageHeight as f64) as u32;
+    match result {
+        Ok(code) => process::exit(code),
+        Err(ref why) if why.is_broken_pipe() => process::exit(0),
+        Err(why) => {
+            report_error(&why, subcommand.is_present("verbose"));
+            process::exit(why.exit_code());
+        }
+    }
+}
 
-    (pRasterX, pRasterY)
+/* Whether the first positional argument is something other than a
+   subcommand, skipping options and the values they take. Files named like
+   a subcommand can still be rendered with --object. */
+fn is_implicit_render(args: &[OsString]) -> bool {
+    let mut rest = args.iter().skip(1).map(|arg| arg.to_string_lossy());
+    while let Some(arg) = rest.next() {
+        if arg == "--" {
+            return true;
+        } else if HELP_OPTIONS.contains(&&*arg) {
+            return false;
+        } else if arg.starts_with("--") {
+            if VALUE_OPTIONS.contains(&&*arg) {
+                rest.next();
+            }
+        } else if arg.starts_with('-') && arg.len() > 1 {
+            /* A cluster such as -vj takes the next argument as the value of
+               its last option, unless the value is attached as in -jfile. */
+            let shorts = &arg[1..];
+            if shorts.find(|c: char| VALUE_SHORTS.contains(&c)) == Some(shorts.len() - 1) {
+                rest.next();
+            }
+        } else {
+            return !SUBCOMMANDS.contains(&&*arg);
+        }
+    }
+    args.len() > 1
 }
 
-fn main() {
-    let mut object: Option<Object> = None;
-    let mut output: Option<String> = None;
+fn app() -> App<'static, 'static> {
+    let file = Arg::with_name("file")
+        .value_name("FILE")
+        .help("Sets the object file, like --object, or - to read it from standard input")
+        .index(1);
 
-    let matches = App::new("sticks")
+    App::new("sticks")
         .version("0.0.1")
         .author("Nick Goote <ngoote@gmail.com>")
-        .about("Renders a wireframe model of the xtree object.")
-        .arg(Arg::with_name("material")
-            .short("m")
-            .long("material")
-            .value_name("FILE")
-            .help("Sets a material file")
-            .takes_value(true)
-            .multiple(true))
+        .about("Renders, inspects, converts and validates Wavefront object files.")
+        .after_help(EXIT_CODES)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
         .arg(Arg::with_name("object")
             .short("j")
             .long("object")
             .value_name("FILE")
-            .help("Sets the object file")
+            .help("Sets the object file, or - to read it from standard input")
+            .takes_value(true)
+            .global(true))
+        .arg(Arg::with_name("material")
+            .short("m")
+            .long("material")
+            .value_name("FILE")
+            .help("Sets a material file, or - to read it from standard input, instead of the mtllib files next to the object file")
             .takes_value(true)
-            .required(true))
+            .multiple(true)
+            .number_of_values(1)
+            .global(true))
         .arg(Arg::with_name("output")
-            .short("o`")
+            .short("o")
             .long("output")
             .value_name("FILE")
-            .help("Sets the output file")
+            .help("Sets the output file, or - for standard output")
+            .takes_value(true)
+            .global(true))
+        .arg(Arg::with_name("format")
+            .short("f")
+            .long("format")
+            .value_name("FORMAT")
+            .help("Sets the output format: svg, pdf, eps, dxf, tikz or terminal for render, obj for convert and text or json for validate. Without it, the format is told from the extension of the output file")
             .takes_value(true)
-            .required(true))
-        .get_matches();
+            .global(true))
+        .arg(Arg::with_name("verbose")
+            .short("v")
+            .long("verbose")
+            .help("Prints what is being done to standard error")
+            .multiple(true)
+            .global(true))
+        .arg(Arg::with_name("quiet")
+            .short("q")
+            .long("quiet")
+            .help("Only prints errors")
+            .conflicts_with("verbose")
+            .global(true))
+        .subcommand(SubCommand::with_name("render")
+            .about("Renders a wireframe of an object file. This is what sticks does without a subcommand.")
+            .after_help(EXIT_CODES)
+            .arg(file.clone())
+            .arg(Arg::with_name("size")
+                .long("size")
+                .value_name("WIDTHxHEIGHT")
+                .help("Sets the image size in pixels, such as 1920x1080. Terminal output is sized with --cols and --rows instead")
+                .takes_value(true)
+                .default_value("512x512"))
+            .arg(Arg::with_name("margin")
+                .long("margin")
+                .value_name("PIXELS")
+                .help("Leaves this many pixels blank on every side of the image")
+                .takes_value(true)
+                .default_value("0"))
+            .arg(Arg::with_name("background")
+                .long("background")
+                .value_name("COLOR")
+                .help("Fills the background of svg, pdf and eps output with white, black or a hex color such as #ff8000")
+                .takes_value(true))
+            .arg(Arg::with_name("canvas")
+                .long("canvas")
+                .value_name("WIDTHxHEIGHT")
+                .help("Sets the size of the canvas one unit in front of the camera, which is grown to the aspect ratio of the image")
+                .takes_value(true)
+                .default_value("2x2"))
+            .arg(Arg::with_name("fov")
+                .long("fov")
+                .value_name("DEGREES")
+                .help("Sets the field of view across the narrower side of the image, instead of --canvas")
+                .takes_value(true)
+                .conflicts_with("canvas"))
+            .arg(Arg::with_name("remove-hidden-lines")
+                .long("remove-hidden-lines")
+                .help("Leaves out the parts of edges that faces closer to the camera hide, such as for dxf output to cut or plot"))
+            .arg(Arg::with_name("page")
+                .long("page")
+                .value_name("SIZE")
+                .help("Sets the page size of pdf and eps output: a3, a4, a5, letter, legal or a custom size such as 100x150mm")
+                .takes_value(true)
+                .default_value("a4"))
+            .arg(Arg::with_name("stroke-width")
+                .long("stroke-width")
+                .value_name("LENGTH")
+                .help("Sets the stroke width of pdf, eps and tikz output in mm, cm, in or pt")
+                .takes_value(true)
+                .default_value("0.25mm"))
+            .arg(Arg::with_name("layers")
+                .long("layers")
+                .value_name("BY")
+                .help("Sets how dxf output is split into layers: by material, by the first group of each face, or not at all")
+                .takes_value(true)
+                .possible_values(&["material", "group", "none"])
+                .default_value("material"))
+            .arg(Arg::with_name("figure-width")
+                .long("figure-width")
+                .value_name("LENGTH")
+                .help("Sets the width of tikz output in mm, cm, in or pt")
+                .takes_value(true)
+                .default_value("8cm"))
+            .arg(Arg::with_name("cols")
+                .long("cols")
+                .value_name("COLUMNS")
+                .help("Sets the width of terminal output in characters")
+                .takes_value(true))
+            .arg(Arg::with_name("rows")
+                .long("rows")
+                .value_name("ROWS")
+                .help("Sets the height of terminal output in characters")
+                .takes_value(true))
+            .arg(Arg::with_name("ascii")
+                .long("ascii")
+                .help("Draws terminal output with ASCII characters instead of braille"))
+            .args(&weld_args()))
+        .subcommand(SubCommand::with_name("info")
+            .about("Prints the counts, groups, materials, bounds, area, volume, topology and edge lengths of an object file")
+            .after_help(EXIT_CODES)
+            .arg(file.clone()))
+        .subcommand(SubCommand::with_name("convert")
+            .about("Writes an object file back out, after welding its vertices with --weld")
+            .after_help(EXIT_CODES)
+            .arg(file.clone())
+            .args(&weld_args()))
+        .subcommand(SubCommand::with_name("validate")
+            .about("Checks an object file for invalid indices, degenerate triangles, unused vertices, non-manifold edges, inconsistent winding, missing materials and non-finite coordinates")
+            .after_help(EXIT_CODES)
+            .arg(file)
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Writes the report as JSON, like --format json"))
+            .arg(Arg::with_name("strict")
+                .long("strict")
+                .help("Also exits with 1 when there are only warnings")))
+}
+
+fn weld_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("weld")
+            .long("weld")
+            .value_name("EPSILON")
+            .help("Merges vertices at most EPSILON apart and drops the faces this collapses or duplicates")
+            .takes_value(true),
+        Arg::with_name("keep-faces")
+            .long("keep-faces")
+            .requires("weld")
+            .help("Keeps degenerate and duplicate faces when welding"),
+    ]
+}
+
+/* What kind of failure stopped a subcommand, which decides the exit code. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Failure {
+    Usage,
+    Input,
+    Parse,
+    Output,
+}
+
+#[derive(Debug)]
+struct CliError {
+    failure: Failure,
+    message: String,
+    cause: Option<Box<dyn Error>>,
+}
 
-    if let Some(o) = matches.value_of("output") {
-        output = Some(o.to_string());
+impl CliError {
+    fn new(failure: Failure, message: String) -> CliError {
+        CliError{failure, message, cause: None}
+    }
+
+    fn caused_by<E: Error + 'static>(failure: Failure, message: String, cause: E) -> CliError {
+        CliError{failure, message, cause: Some(Box::new(cause))}
+    }
+
+    fn usage(message: String) -> CliError {
+        CliError::new(Failure::Usage, message)
+    }
+
+    fn exit_code(&self) -> i32 {
+        match self.failure {
+            Failure::Usage => 2,
+            Failure::Input => 3,
+            Failure::Parse => 4,
+            Failure::Output => 5,
+        }
+    }
+
+    /* A closed pipe only means that whoever reads the output has seen
+       enough of it, like `sticks info model.obj | head`. */
+    fn is_broken_pipe(&self) -> bool {
+        match self.cause {
+            Some(ref cause) => cause.downcast_ref::<io::Error>().is_some_and(|why| why.kind() == io::ErrorKind::BrokenPipe),
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for CliError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.cause.as_ref().map(|cause| cause.as_ref())
+    }
+}
+
+/* The library reports errors as strings, this lets them be the cause of a
+   `CliError`. */
+#[derive(Debug)]
+struct Message(String);
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for Message {}
+
+/* Prints the error on one line, or with each of its causes on a line of
+   their own with --verbose. */
+fn report_error(why: &CliError, verbose: bool) {
+    if verbose {
+        eprintln!("error: {}", why);
+        let mut cause = why.source();
+        while let Some(c) = cause {
+            eprintln!("  caused by: {}", c);
+            cause = c.source();
+        }
     } else {
-        // This shouldn't happen because this parameter
-        // is marked required.
-        panic!("Failed to supply output file.")
+        match why.source() {
+            Some(cause) => eprintln!("error: {}: {}", why, cause),
+            None => eprintln!("error: {}", why),
+        }
     }
+}
 
-    if let Some(j) = matches.value_of("object") {
-        let mut file = match File::open(j) {
-            Err(why) => panic!("couldn't open {}: {}", j, why.description()),
-            Ok(file) => file,
-        };
+/* Messages about what is being done go to standard error, so that they
+   don't end up in output written to standard output. */
+fn verbose(matches: &ArgMatches, message: &str) {
+    if matches.is_present("verbose") {
+        eprintln!("{}", message);
+    }
+}
 
-        let mut s = String::new();
-        let _ = match file.read_to_string(&mut s) {
-            Err(why) => panic!("couldn't read from {}: {}", j, why.description()),
-            _ => ()
-        };
+/* The --format of a subcommand. Without it, the format is told from the
+   extension of the --output file, or is `default`. Standard output has no
+   extension, so subcommands without a default need --format to write to it. */
+fn format<'a>(matches: &'a ArgMatches, subcommand: &str, allowed: &[&'a str], default: Option<&'a str>) -> Result<&'a str, CliError> {
+    if let Some(format) = matches.value_of("format") {
+        if !allowed.contains(&format) {
+            return Err(CliError::usage(format!("{} can't write {}, use one of: {}", subcommand, format, allowed.join(", "))));
+        }
+        return Ok(format);
+    }
+
+    let output = matches.value_of("output").filter(|&path| path != "-");
+    let inferred = output
+        .and_then(|path| Path::new(path).extension())
+        .and_then(|extension| format_of_extension(&extension.to_string_lossy().to_lowercase()))
+        .and_then(|format| allowed.iter().cloned().find(|&allowed| allowed == format));
+
+    inferred.or(default).ok_or_else(|| {
+        let output = output.unwrap_or("standard output");
+        CliError::usage(format!("{} can't tell the format to write {} in, use --format with one of: {}", subcommand, output, allowed.join(", ")))
+    })
+}
 
-        object = match parse_object(&s) {
-            Err(why) => panic!("{}", why),
-            Ok(obj) => Some(obj),
+fn format_of_extension(extension: &str) -> Option<&'static str> {
+    match extension {
+        "svg" => Some("svg"),
+        "pdf" => Some("pdf"),
+        "eps" | "ps" => Some("eps"),
+        "dxf" => Some("dxf"),
+        "tex" | "tikz" => Some("tikz"),
+        "obj" => Some("obj"),
+        "json" => Some("json"),
+        "txt" => Some("text"),
+        _ => None,
+    }
+}
+
+fn parse_number<T: FromStr>(value: &str, what: &str) -> Result<T, CliError> {
+    value.parse::<T>().map_err(|_| CliError::usage(format!("invalid {}: {}", what, value)))
+}
+
+/* A size such as 1920x1080, where both sides have to be positive. */
+fn parse_size<T: FromStr + PartialOrd + Default>(value: &str, what: &str) -> Result<(T, T), CliError> {
+    let invalid = || CliError::usage(format!("invalid {}: {}, expected a size such as 1920x1080", what, value));
+    let mut parts = value.splitn(2, 'x');
+    let width = parts.next().and_then(|w| w.trim().parse::<T>().ok()).ok_or_else(invalid)?;
+    let height = parts.next().and_then(|h| h.trim().parse::<T>().ok()).ok_or_else(invalid)?;
+    if width > T::default() && height > T::default() {
+        Ok((width, height))
+    } else {
+        Err(invalid())
+    }
+}
+
+fn object_path<'a>(matches: &'a ArgMatches) -> Result<&'a str, CliError> {
+    let path = match (matches.value_of("file"), matches.value_of("object")) {
+        (Some(_), Some(_)) => return Err(CliError::usage("the object file is given both as FILE and with --object".to_string())),
+        (Some(path), None) | (None, Some(path)) => path,
+        (None, None) => return Err(CliError::usage("no object file given".to_string())),
+    };
+
+    let materials = matches.values_of("material").into_iter().flatten();
+    if Some(path).into_iter().chain(materials).filter(|&path| path == "-").count() > 1 {
+        return Err(CliError::usage("only one of the object and material files can be read from standard input".to_string()));
+    }
+    Ok(path)
+}
+
+/* `-` stands for standard input and output. */
+fn display_name(path: &str) -> &str {
+    if path == "-" { "standard input" } else { path }
+}
+
+fn read_file(path: &str) -> Result<String, CliError> {
+    let mut s = String::new();
+    let result = if path == "-" {
+        io::stdin().read_to_string(&mut s)
+    } else {
+        File::open(path).and_then(|mut file| file.read_to_string(&mut s))
+    };
+    result.map_err(|why| CliError::caused_by(Failure::Input, format!("couldn't read {}", display_name(path)), why))?;
+    Ok(s)
+}
+
+fn read_object<'a>(matches: &'a ArgMatches) -> Result<(&'a str, String), CliError> {
+    let path = object_path(matches)?;
+    verbose(matches, &format!("reading {}", display_name(path)));
+    Ok((path, read_file(path)?))
+}
+
+fn read_mesh(matches: &ArgMatches) -> Result<Mesh, CliError> {
+    let weld = match matches.value_of("weld") {
+        Some(value) => match parse_number::<f64>(value, "weld epsilon")? {
+            epsilon if epsilon.is_finite() && epsilon >= 0.0 => Some(epsilon),
+            _ => return Err(CliError::usage(format!("invalid weld epsilon: {}, expected a distance of 0 or more", value))),
+        },
+        None => None,
+    };
+
+    let (path, s) = read_object(matches)?;
+    let mut mesh = parse_mesh(&s).map_err(|why| CliError::caused_by(Failure::Parse, format!("couldn't parse {}", display_name(path)), Message(why)))?;
+    verbose(matches, &format!("read {} vertices and {} faces", mesh.positions.len(), mesh.faces.len()));
+
+    if let Some(epsilon) = weld {
+        let keep_faces = matches.is_present("keep-faces");
+        let options = WeldOptions{epsilon, remove_degenerate: !keep_faces, remove_duplicates: !keep_faces};
+
+        let report = mesh.weld(&options);
+        if !matches.is_present("quiet") {
+            eprintln!("welded {} vertices, removed {} degenerate and {} duplicate faces",
+                      report.merged_vertices, report.degenerate_faces, report.duplicate_faces);
+        }
+    }
+    Ok(mesh)
+}
+
+fn read_materials(matches: &ArgMatches) -> Result<Vec<Material>, CliError> {
+    let mut materials: Vec<Material> = Vec::new();
+    for m in matches.values_of("material").into_iter().flatten() {
+        verbose(matches, &format!("reading {}", display_name(m)));
+        let parsed = parse_materials(&read_file(m)?)
+            .map_err(|why| CliError::caused_by(Failure::Parse, format!("couldn't parse {}", display_name(m)), Message(why)))?;
+        materials.extend(parsed);
+    }
+    Ok(materials)
+}
+
+/* Writes to the --output file, or to standard output without one or for
+   `-`. */
+fn with_output<F>(matches: &ArgMatches, write: F) -> Result<(), CliError> where F: FnOnce(&mut dyn Write) -> io::Result<()> {
+    match matches.value_of("output").filter(|&path| path != "-") {
+        Some(path) => {
+            verbose(matches, &format!("writing {}", path));
+            let mut file = File::create(path)
+                .map_err(|why| CliError::caused_by(Failure::Output, format!("couldn't create {}", path), why))?;
+            write(&mut file).and_then(|_| file.flush())
+                .map_err(|why| CliError::caused_by(Failure::Output, format!("couldn't write {}", path), why))
+        },
+        None => {
+            let stdout = io::stdout();
+            let mut lock = stdout.lock();
+            write(&mut lock).and_then(|_| lock.flush())
+                .map_err(|why| CliError::caused_by(Failure::Output, "couldn't write to standard output".to_string(), why))
+        },
+    }
+}
+
+fn render(matches: &ArgMatches) -> Result<i32, CliError> {
+    if matches.value_of("format") != Some("terminal") && !matches.is_present("output") {
+        return Err(CliError::usage("render needs an --output file, or - for standard output".to_string()));
+    }
+    let format = format(matches, "render", &["svg", "pdf", "eps", "dxf", "tikz", "terminal"], None)?;
+
+    let backend: Box<dyn Backend> = match format {
+        "pdf" | "eps" => {
+            let page = Page::parse(matches.value_of("page").unwrap()).map_err(CliError::usage)?;
+            let stroke_width = parse_length(matches.value_of("stroke-width").unwrap()).map_err(CliError::usage)?;
+            if format == "pdf" {
+                Box::new(Pdf{page, stroke_width})
+            } else {
+                Box::new(Eps{page, stroke_width})
+            }
+        },
+        "dxf" => {
+            let layers = match matches.value_of("layers").unwrap() {
+                "material" => Layers::Material,
+                "group" => Layers::Group,
+                _ => Layers::None,
+            };
+            Box::new(Dxf{layers})
+        },
+        "tikz" => {
+            let figure_width = parse_length(matches.value_of("figure-width").unwrap()).map_err(CliError::usage)?;
+            let stroke_width = parse_length(matches.value_of("stroke-width").unwrap()).map_err(CliError::usage)?;
+            Box::new(Tikz{figure_width, stroke_width})
+        },
+        "terminal" => {
+            let charset = if matches.is_present("ascii") { Charset::Ascii } else { Charset::Braille };
+            Box::new(Terminal{charset})
+        },
+        _ => Box::new(Svg),
+    };
+
+    let (image_width, image_height) = if format == "terminal" {
+        let charset = if matches.is_present("ascii") { Charset::Ascii } else { Charset::Braille };
+
+        let (terminal_cols, terminal_rows) = terminal_size().unwrap_or((80, 24));
+        let cols = match matches.value_of("cols") {
+            Some(c) => parse_number::<usize>(c, "number of columns")?,
+            None => terminal_cols,
+        };
+        let rows = match matches.value_of("rows") {
+            Some(r) => parse_number::<usize>(r, "number of rows")?,
+            /* Leave a row free for the shell prompt when sizing to the terminal. */
+            None => terminal_rows.saturating_sub(1).max(1),
         };
-    }  else {
-        // This shouldn't happen because this parameter
-        // is marked required.
-        panic!("Failed to supply object file.")
+        pixel_size(cols, rows, charset)
+    } else {
+        parse_size::<u32>(matches.value_of("size").unwrap(), "image size")?
+    };
+
+    let margin = parse_number::<u32>(matches.value_of("margin").unwrap(), "margin")?;
+    if margin.saturating_mul(2) >= image_width.min(image_height) {
+        return Err(CliError::usage(format!("a margin of {} leaves nothing of a {}x{} image", margin, image_width, image_height)));
+    }
+    let background = match matches.value_of("background") {
+        Some(color) => Some(Rgb::parse(color).map_err(CliError::usage)?),
+        None => None,
+    };
+
+    let mesh = read_mesh(matches)?;
+    let materials = read_materials(matches)?;
+
+    /* The camera is placed at a fixed position, and its matrix can always
+       be inverted. Its canvas is fitted to the image inside the margin, so
+       the renderer is set up first and given the camera once that is known. */
+    let camera_to_world: Matrix44<f64> = Matrix44::new(0.871214, 0.0, -0.490904, 0.0, -0.192902, 0.919559, -0.342346, 0.0, 0.451415, 0.392953, 0.801132, 0.0, 14.777467, 29.361945, 27.993464, 1.0);
+    let default_camera = Camera::new(camera_to_world, 2.0, 2.0).expect("the camera matrix is invertible");
+    let mut renderer = Renderer::new(default_camera, image_width, image_height).with_margin(margin);
+    if let Some(color) = background {
+        renderer = renderer.with_background(color);
+    }
+    if matches.is_present("remove-hidden-lines") {
+        renderer = renderer.with_hidden_lines_removed();
     }
 
-    let mut output_file = match File::create(output.unwrap()) {
-        Err(why) => panic!("couldn't open {}: {}", "File to write", why.description()),
-        Ok(output_file) => output_file,
+    /* Each ASCII pixel is a character cell, about twice as tall as wide, so
+       fit the canvas to how the image looks rather than to its pixels. */
+    let (inner_width, inner_height) = renderer.inner_size();
+    let (fit_width, fit_height) = if format == "terminal" && matches.is_present("ascii") {
+        (inner_width, inner_height * 2)
+    } else {
+        (inner_width, inner_height)
+    };
+    renderer.camera = match matches.value_of("fov") {
+        Some(value) => {
+            let fov = parse_number::<f64>(value, "field of view")?;
+            if !fov.is_finite() || fov < MIN_FIELD_OF_VIEW {
+                return Err(CliError::usage(format!("invalid field of view: {}, expected at least {} degrees", value, MIN_FIELD_OF_VIEW)));
+            }
+            Camera::with_field_of_view(camera_to_world, fov.to_radians(), fit_width, fit_height).map_err(CliError::usage)?
+        },
+        None => {
+            let value = matches.value_of("canvas").unwrap();
+            let (canvas_width, canvas_height) = parse_size::<f64>(value, "canvas size")?;
+            if ![canvas_width, canvas_height].iter().all(|side| side.is_finite() && *side >= MIN_CANVAS_SIDE) {
+                return Err(CliError::usage(format!("invalid canvas size: {}, expected sides of at least {}", value, MIN_CANVAS_SIDE)));
+            }
+            let (canvas_width, canvas_height) = fit_canvas(canvas_width, canvas_height, fit_width, fit_height);
+            Camera::new(camera_to_world, canvas_width, canvas_height).expect("the camera matrix is invertible")
+        },
     };
 
-    output_file.write_all(b"<svg version=\"1.1\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" xmlns=\"http://www.w3.org/2000/svg\" height=\"512\" width=\"512\">");
+    with_output(matches, |out| renderer.render(&mesh, &materials, backend.as_ref(), out))?;
+    Ok(0)
+}
 
-    let cameraToWorld: Matrix44<f64> = Matrix44::new(0.871214, 0.0, -0.490904, 0.0, -0.192902, 0.919559, -0.342346, 0.0, 0.451415, 0.392953, 0.801132, 0.0, 14.777467, 29.361945, 27.993464, 1.0);
-    let worldToCamera: Matrix44<f64> = cameraToWorld.clone().inverse();
-    let canvasWidth: f64 = 2.0;
-    let canvasHeight: f64 = 2.0;
-    let imageWidth: u32 = 512;
-    let imageHeight: u32 = 512;
-    let uobject = object.unwrap();
+fn info(matches: &ArgMatches) -> Result<i32, CliError> {
+    format(matches, "info", &["text"], Some("text"))?;
+    let stats = read_mesh(matches)?.stats();
+    with_output(matches, |out| write!(out, "{}", stats))?;
+    Ok(0)
+}
 
-    for i in 0..uobject.triangles.len() {
-        let v0Worldy = uobject.triangles[i].v1;
-        let ref v0World: Vec3<f64> = Vec3::new(v0Worldy.x, v0Worldy.y, v0Worldy.z);
-        let v1Worldy = uobject.triangles[i].v2;
-        let ref v1World: Vec3<f64> = Vec3::new(v1Worldy.x, v1Worldy.y, v1Worldy.z);
-        let v2Worldy = uobject.triangles[i].v3;
-        let ref v2World: Vec3<f64> = Vec3::new(v2Worldy.x, v2Worldy.y, v2Worldy.z);
+fn convert(matches: &ArgMatches) -> Result<i32, CliError> {
+    format(matches, "convert", &["obj"], Some("obj"))?;
+    let mesh = read_mesh(matches)?;
+    with_output(matches, |out| write_mesh(&mesh, out))?;
+    Ok(0)
+}
 
-        let (v0RasterX, v0RasterY) = computePixelCoordinates(v0World, &worldToCamera, canvasWidth, canvasHeight, imageWidth, imageHeight);
-        let (v1RasterX, v1RasterY) = computePixelCoordinates(v1World, &worldToCamera, canvasWidth, canvasHeight, imageWidth, imageHeight);
-        let (v2RasterX, v2RasterY) = computePixelCoordinates(v2World, &worldToCamera, canvasWidth, canvasHeight, imageWidth, imageHeight);
+/* Returns 0 when the object is valid and 1 when problems were found.
+   Problems with the object or its materials go in the report instead of
+   failing. */
+fn validate(matches: &ArgMatches) -> Result<i32, CliError> {
+    let json = matches.is_present("json") || format(matches, "validate", &["text", "json"], Some("text"))? == "json";
+    let (path, s) = read_object(matches)?;
 
-        output_file.write_fmt(format_args!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" style=\"stroke:rgb(0,0,0);stroke-width:1\" />", v0RasterX, v0RasterY, v1RasterX, v1RasterY));
-        output_file.write_fmt(format_args!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" style=\"stroke:rgb(0,0,0);stroke-width:1\" />", v1RasterX, v1RasterY, v2RasterX, v2RasterY));
-        output_file.write_fmt(format_args!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" style=\"stroke:rgb(0,0,0);stroke-width:1\" />", v2RasterX, v2RasterY, v0RasterX, v0RasterY));
+    let report = match parse_mesh_source(&s) {
+        Err(why) => Report{issues: vec![Issue::error("parse-error", None, why)]},
+        Ok(parsed) => {
+            let mut issues = Vec::new();
+            let libraries: Vec<String> = match matches.values_of("material") {
+                Some(files) => files.map(|m| m.to_string()).collect(),
+                None => {
+                    let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+                    parsed.mesh.material_libraries.iter().map(|m| directory.join(m).to_string_lossy().into_owned()).collect()
+                }
+            };
+
+            let mut materials: Vec<Material> = Vec::new();
+            for library in libraries {
+                let s = match read_file(&library) {
+                    Ok(s) => s,
+                    Err(why) => {
+                        let cause = why.source().map_or(String::new(), |cause| format!(": {}", cause));
+                        issues.push(Issue::error("missing-material-library", None, format!("{}{}", why, cause)));
+                        continue;
+                    }
+                };
+                match parse_materials(&s) {
+                    Err(why) => issues.push(Issue::error("parse-error", None, format!("{}: {}", library, why))),
+                    Ok(parsed) => materials.extend(parsed),
+                }
+            }
+
+            let mut report = validate_source(&parsed, Some(&materials));
+            report.issues.extend(issues);
+            report.sort();
+            report
+        }
+    };
+
+    if json {
+        with_output(matches, |out| writeln!(out, "{}", report.to_json()))?;
+    } else if !matches.is_present("quiet") || report.has_errors() {
+        with_output(matches, |out| writeln!(out, "{}", report))?;
     }
 
-    output_file.write_all(b"</svg>");
-}
\ No newline at end of file
+    if report.has_errors() || (matches.is_present("strict") && report.warning_count() > 0) {
+        Ok(1)
+    } else {
+        Ok(0)
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use mesh::Mesh;
+
+/// The side of an edge that belongs to one face, pointing from `origin` to
+/// the origin of `next`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HalfEdge {
+    pub origin: usize,
+    pub face: usize,
+    pub next: usize,
+    /// The opposite half edge, only set when the edge is shared by exactly
+    /// two consistently wound faces.
+    pub twin: Option<usize>,
+    pub edge: usize,
+}
+
+/// Adjacency of the faces of a `Mesh`.
+///
+/// The half edges of face `f` are `3 * f`, `3 * f + 1` and `3 * f + 2`, in
+/// the face's winding order. Non-manifold meshes are accepted: an edge
+/// keeps all of its half edges, they just don't get a twin.
+#[derive(Debug, Clone)]
+pub struct HalfEdgeMesh {
+    half_edges: Vec<HalfEdge>,
+    edges: Vec<Vec<usize>>,
+    edge_ids: HashMap<(usize, usize), usize>,
+    outgoing: Vec<Vec<usize>>,
+}
+
+impl HalfEdgeMesh {
+    pub fn new(mesh: &Mesh) -> HalfEdgeMesh {
+        let mut half_edges = Vec::with_capacity(mesh.faces.len() * 3);
+        let mut edges: Vec<Vec<usize>> = Vec::new();
+        let mut edge_ids = HashMap::new();
+        let mut outgoing = vec![Vec::new(); mesh.positions.len()];
+
+        for (f, face) in mesh.faces.iter().enumerate() {
+            for k in 0..3 {
+                let h = half_edges.len();
+                let (a, b) = (face.v[k], face.v[(k + 1) % 3]);
+                let edge = *edge_ids.entry(edge_key(a, b)).or_insert_with(|| {
+                    edges.push(Vec::new());
+                    edges.len() - 1
+                });
+
+                edges[edge].push(h);
+                outgoing[a].push(h);
+                half_edges.push(HalfEdge{origin: a, face: f, next: 3 * f + (k + 1) % 3, twin: None, edge});
+            }
+        }
+
+        for edge in &edges {
+            if let [h1, h2] = edge[..] {
+                if half_edges[h1].origin != half_edges[h2].origin {
+                    half_edges[h1].twin = Some(h2);
+                    half_edges[h2].twin = Some(h1);
+                }
+            }
+        }
+
+        HalfEdgeMesh{half_edges, edges, edge_ids, outgoing}
+    }
+
+    pub fn half_edge(&self, h: usize) -> &HalfEdge {
+        &self.half_edges[h]
+    }
+
+    pub fn half_edge_count(&self) -> usize {
+        self.half_edges.len()
+    }
+
+    pub fn destination(&self, h: usize) -> usize {
+        self.half_edges[self.half_edges[h].next].origin
+    }
+
+    pub fn prev(&self, h: usize) -> usize {
+        self.half_edges[self.half_edges[h].next].next
+    }
+
+    pub fn face_half_edges(&self, f: usize) -> [usize; 3] {
+        [3 * f, 3 * f + 1, 3 * f + 2]
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// The edge between two vertices, in either direction.
+    pub fn edge(&self, a: usize, b: usize) -> Option<usize> {
+        self.edge_ids.get(&edge_key(a, b)).cloned()
+    }
+
+    pub fn edge_vertices(&self, e: usize) -> (usize, usize) {
+        let h = self.edges[e][0];
+        (self.half_edges[h].origin, self.destination(h))
+    }
+
+    pub fn edge_half_edges(&self, e: usize) -> &[usize] {
+        &self.edges[e]
+    }
+
+    pub fn edge_faces(&self, e: usize) -> Vec<usize> {
+        self.edges[e].iter().map(|&h| self.half_edges[h].face).collect()
+    }
+
+    pub fn is_boundary_edge(&self, e: usize) -> bool {
+        self.edges[e].len() == 1
+    }
+
+    /// Whether the edge is shared by more than two faces.
+    pub fn is_non_manifold_edge(&self, e: usize) -> bool {
+        self.edges[e].len() > 2
+    }
+
+    /// Whether the edge is shared by two faces that run along it in the same
+    /// direction, i.e. one of them is wound the wrong way.
+    pub fn is_misoriented_edge(&self, e: usize) -> bool {
+        match self.edges[e][..] {
+            [h1, h2] => self.half_edges[h1].origin == self.half_edges[h2].origin,
+            _ => false,
+        }
+    }
+
+    /// The faces sharing at least one edge with `f`, sorted.
+    pub fn face_neighbors(&self, f: usize) -> Vec<usize> {
+        let mut neighbors: Vec<usize> = self.face_half_edges(f).iter()
+            .flat_map(|&h| self.edge_faces(self.half_edges[h].edge))
+            .filter(|&g| g != f)
+            .collect();
+        neighbors.sort();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// The faces around the vertex, sorted.
+    pub fn vertex_faces(&self, v: usize) -> Vec<usize> {
+        let mut faces: Vec<usize> = self.outgoing[v].iter().map(|&h| self.half_edges[h].face).collect();
+        faces.sort();
+        faces.dedup();
+        faces
+    }
+
+    /// The vertices connected to `v` by an edge. They are in order around
+    /// the vertex when it is manifold, and sorted otherwise.
+    pub fn one_ring(&self, v: usize) -> Vec<usize> {
+        let outgoing = &self.outgoing[v];
+        if outgoing.is_empty() {
+            return Vec::new();
+        }
+
+        // On a boundary the walk has to start at the first face of the fan,
+        // whose outgoing half edge has no twin.
+        let start = outgoing.iter().cloned()
+            .find(|&h| self.half_edges[h].twin.is_none())
+            .unwrap_or(outgoing[0]);
+
+        let mut ring = Vec::with_capacity(outgoing.len() + 1);
+        let mut visited = 0;
+        let mut h = start;
+        loop {
+            ring.push(self.destination(h));
+            visited += 1;
+
+            let prev = self.prev(h);
+            match self.half_edges[prev].twin {
+                Some(twin) if twin == start => break,
+                Some(twin) if visited < outgoing.len() => h = twin,
+                Some(_) => break,
+                None => {
+                    ring.push(self.half_edges[prev].origin);
+                    break;
+                }
+            }
+        }
+
+        if visited == outgoing.len() {
+            ring
+        } else {
+            let mut ring: Vec<usize> = outgoing.iter()
+                .flat_map(|&h| vec![self.destination(h), self.half_edges[self.prev(h)].origin])
+                .collect();
+            ring.sort();
+            ring.dedup();
+            ring
+        }
+    }
+
+    /// The boundary edges chained into loops of vertices, following the
+    /// winding of their faces.
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let mut boundary: HashMap<usize, Vec<usize>> = HashMap::new();
+        for edge in self.edges.iter().filter(|edge| edge.len() == 1) {
+            boundary.entry(self.half_edges[edge[0]].origin).or_default().push(edge[0]);
+        }
+
+        let mut starts: Vec<usize> = boundary.values().flat_map(|hs| hs.iter().cloned()).collect();
+        starts.sort();
+
+        let mut visited = vec![false; self.half_edges.len()];
+        let mut loops = Vec::new();
+        for start in starts {
+            if visited[start] {
+                continue;
+            }
+
+            let mut vertices = Vec::new();
+            let mut h = Some(start);
+            while let Some(current) = h {
+                visited[current] = true;
+                vertices.push(self.half_edges[current].origin);
+                h = match self.next_boundary(current) {
+                    Some(next) => Some(next).filter(|&next| !visited[next]),
+                    None => boundary.get(&self.destination(current))
+                        .and_then(|hs| hs.iter().cloned().find(|&next| !visited[next])),
+                };
+            }
+            loops.push(vertices);
+        }
+        loops
+    }
+
+    /// The boundary half edge after `h` in the same fan of faces around the
+    /// destination of `h`, so that loops don't jump across a vertex where
+    /// two fans meet.
+    fn next_boundary(&self, h: usize) -> Option<usize> {
+        let mut next = self.half_edges[h].next;
+        for _ in 0..self.outgoing[self.destination(h)].len() {
+            match self.half_edges[next].twin {
+                Some(twin) => next = self.half_edges[twin].next,
+                None => return Some(next).filter(|&next| self.is_boundary_edge(self.half_edges[next].edge)),
+            }
+        }
+        None
+    }
+
+    pub fn non_manifold_edges(&self) -> Vec<usize> {
+        (0..self.edges.len()).filter(|&e| self.is_non_manifold_edge(e)).collect()
+    }
+
+    /// The vertices whose faces don't form a single fan, e.g. the shared
+    /// corner of two triangles that only touch at that point.
+    pub fn non_manifold_vertices(&self) -> Vec<usize> {
+        (0..self.outgoing.len()).filter(|&v| self.fan_count(v) > 1).collect()
+    }
+
+    /// The number of groups of faces around `v` that are connected through
+    /// edges of `v`.
+    fn fan_count(&self, v: usize) -> usize {
+        let faces = self.vertex_faces(v);
+        let mut parents: Vec<usize> = (0..faces.len()).collect();
+
+        fn root(parents: &mut [usize], i: usize) -> usize {
+            let mut i = i;
+            while parents[i] != i {
+                parents[i] = parents[parents[i]];
+                i = parents[i];
+            }
+            i
+        }
+
+        for &h in &self.outgoing[v] {
+            let edge_faces = self.edge_faces(self.half_edges[h].edge);
+            let first = faces.binary_search(&edge_faces[0]).unwrap();
+            for face in &edge_faces[1..] {
+                let other = faces.binary_search(face).unwrap();
+                let (a, b) = (root(&mut parents, first), root(&mut parents, other));
+                parents[a] = b;
+            }
+        }
+
+        (0..faces.len()).filter(|&i| root(&mut parents, i) == i).count()
+    }
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b { (a, b) } else { (b, a) }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use wavefront::parse_mesh;
+    use super::HalfEdgeMesh;
+
+    fn half_edges(string: &str) -> HalfEdgeMesh {
+        HalfEdgeMesh::new(&parse_mesh(string).unwrap())
+    }
+
+    const SQUARE: &str = "v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 1.0 0.0
+v 0.0 1.0 0.0
+f 1 2 3
+f 1 3 4
+";
+
+    const TETRAHEDRON: &str = "v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 0.0 0.0 1.0
+f 1 3 2
+f 1 2 4
+f 2 3 4
+f 3 1 4
+";
+
+    #[test]
+    fn square_should_share_its_diagonal() {
+        let mesh = half_edges(SQUARE);
+
+        assert_eq!(6, mesh.half_edge_count());
+        assert_eq!(5, mesh.edge_count());
+
+        let diagonal = mesh.edge(2, 0).unwrap();
+        assert_eq!(Some(diagonal), mesh.edge(0, 2));
+        assert_eq!(vec![0, 1], mesh.edge_faces(diagonal));
+        assert!(!mesh.is_boundary_edge(diagonal));
+        assert!(mesh.is_boundary_edge(mesh.edge(0, 1).unwrap()));
+        assert_eq!(None, mesh.edge(1, 3));
+
+        assert_eq!(Some(3), mesh.half_edge(2).twin);
+        assert_eq!(Some(2), mesh.half_edge(3).twin);
+        assert_eq!(None, mesh.half_edge(0).twin);
+        assert_eq!(2, mesh.prev(0));
+        assert_eq!(1, mesh.destination(0));
+
+        assert_eq!(vec![1], mesh.face_neighbors(0));
+        assert_eq!(vec![0, 1], mesh.vertex_faces(0));
+        assert_eq!(vec![0], mesh.vertex_faces(1));
+    }
+
+    #[test]
+    fn one_ring_should_be_ordered_around_the_vertex() {
+        let square = half_edges(SQUARE);
+        assert_eq!(vec![1, 2, 3], square.one_ring(0));
+        assert_eq!(vec![2, 0], square.one_ring(1));
+
+        let tetrahedron = half_edges(TETRAHEDRON);
+        for v in 0..4 {
+            let mut ring = tetrahedron.one_ring(v);
+            assert_eq!(3, ring.len());
+            ring.sort();
+            assert_eq!((0..4).filter(|&w| w != v).collect::<Vec<_>>(), ring);
+        }
+    }
+
+    #[test]
+    fn boundary_loops_should_follow_the_winding() {
+        assert_eq!(vec![vec![0, 1, 2, 3]], half_edges(SQUARE).boundary_loops());
+
+        let tetrahedron = half_edges(TETRAHEDRON);
+        assert!(tetrahedron.boundary_loops().is_empty());
+        assert_eq!(6, tetrahedron.edge_count());
+        assert!((0..12).all(|h| tetrahedron.half_edge(h).twin.is_some()));
+    }
+
+    #[test]
+    fn closed_manifold_should_have_nothing_to_report() {
+        let tetrahedron = half_edges(TETRAHEDRON);
+
+        assert!(tetrahedron.non_manifold_edges().is_empty());
+        assert!(tetrahedron.non_manifold_vertices().is_empty());
+        assert!((0..6).all(|e| !tetrahedron.is_misoriented_edge(e)));
+    }
+
+    #[test]
+    fn edge_with_three_faces_should_be_non_manifold() {
+        let mesh = half_edges("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 0.0 -1.0 0.0
+v 0.0 0.0 1.0
+f 1 2 3
+f 2 1 4
+f 1 2 5
+");
+
+        let shared = mesh.edge(0, 1).unwrap();
+        assert_eq!(vec![shared], mesh.non_manifold_edges());
+        assert_eq!(vec![0, 1, 2], mesh.edge_faces(shared));
+        assert_eq!(None, mesh.half_edge(0).twin);
+        assert_eq!(vec![1, 2], mesh.face_neighbors(0));
+    }
+
+    #[test]
+    fn vertex_shared_by_two_fans_should_be_non_manifold() {
+        let mesh = half_edges("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 1.0 0.0
+v -1.0 0.0 0.0
+v -1.0 -1.0 0.0
+f 1 2 3
+f 1 4 5
+");
+
+        assert_eq!(vec![0], mesh.non_manifold_vertices());
+        assert!(mesh.non_manifold_edges().is_empty());
+        assert_eq!(vec![1, 2, 3, 4], mesh.one_ring(0));
+        assert_eq!(2, mesh.boundary_loops().len());
+    }
+
+    #[test]
+    fn flipped_face_should_make_its_edges_misoriented() {
+        let mesh = half_edges("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 1.0 0.0
+v 0.0 1.0 0.0
+f 1 2 3
+f 1 4 3
+");
+
+        let diagonal = mesh.edge(0, 2).unwrap();
+        assert!(mesh.is_misoriented_edge(diagonal));
+        assert_eq!(None, mesh.half_edge(2).twin);
+        assert_eq!(vec![1], mesh.face_neighbors(0));
+    }
+}
@@ -0,0 +1,197 @@
+pub mod half_edge;
+pub mod stats;
+pub mod weld;
+
+use std::slice;
+use wavefront::object_parser::{Object, Triangle, Vertex};
+
+pub use self::half_edge::{HalfEdge, HalfEdgeMesh};
+pub use self::stats::{EdgeLengths, MeshStats};
+pub use self::weld::{WeldOptions, WeldReport};
+
+/// A face of a `Mesh`, as indices into the mesh's shared arrays.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Face {
+    /// Corners, as indices into `Mesh::positions`.
+    pub v: [usize; 3],
+    /// Texture coordinates of each corner, into `Mesh::texcoords`.
+    pub vt: [Option<usize>; 3],
+    /// Normals of each corner, into `Mesh::normals`.
+    pub vn: [Option<usize>; 3],
+    /// Index into `Mesh::materials`, or `None` before any `usemtl` and after
+    /// one without a name.
+    pub material: Option<usize>,
+    /// Index into `Mesh::group_lists`, or `None` before any `g` and after
+    /// `g default`.
+    pub groups: Option<usize>,
+}
+
+/// A triangle mesh that shares its vertices between faces. Material names
+/// and group lists are interned, so each face only holds their ids.
+///
+/// Faces are expected to only refer to existing vertices, materials and
+/// group lists, which is always the case for meshes from `parse_mesh`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<Vertex>,
+    pub texcoords: Vec<Vertex>,
+    pub normals: Vec<Vertex>,
+    pub faces: Vec<Face>,
+    pub materials: Vec<String>,
+    pub group_lists: Vec<Vec<String>>,
+    /// The `mtllib` files named by the object file.
+    pub material_libraries: Vec<String>,
+}
+
+impl Mesh {
+    pub fn material_id(&self, name: &str) -> Option<usize> {
+        self.materials.iter().position(|material| material == name)
+    }
+
+    /// The face's material name, or `""` when it has none, like
+    /// `Triangle::materialName`.
+    pub fn material_name(&self, face: &Face) -> &str {
+        face.material.map_or("", |id| &self.materials[id])
+    }
+
+    pub fn groups(&self, face: &Face) -> &[String] {
+        face.groups.map_or(&[], |id| &self.group_lists[id])
+    }
+
+    /// The face with copies of its vertices, as `parse_object` would build it.
+    pub fn triangle(&self, face: &Face) -> Triangle {
+        let optional = |list: &[Vertex], index: Option<usize>| index.map(|i| list[i]);
+
+        Triangle{
+            v1: self.positions[face.v[0]],
+            v2: self.positions[face.v[1]],
+            v3: self.positions[face.v[2]],
+            vt1: optional(&self.texcoords, face.vt[0]),
+            vt2: optional(&self.texcoords, face.vt[1]),
+            vt3: optional(&self.texcoords, face.vt[2]),
+            vn1: optional(&self.normals, face.vn[0]),
+            vn2: optional(&self.normals, face.vn[1]),
+            vn3: optional(&self.normals, face.vn[2]),
+            materialName: self.material_name(face).to_string(),
+            groups: face.groups,
+        }
+    }
+
+    /// Builds the triangle of each face as it is needed, for code written
+    /// against `Object::triangles`.
+    pub fn triangles(&self) -> Triangles<'_> {
+        Triangles{mesh: self, faces: self.faces.iter()}
+    }
+
+    pub fn to_object(&self) -> Object {
+        Object{
+            triangles: self.triangles().collect(),
+            raw_vertices: self.positions.clone(),
+            raw_vertices_texture: self.texcoords.clone(),
+            raw_vertices_normals: self.normals.clone(),
+            group_lists: self.group_lists.clone(),
+        }
+    }
+}
+
+impl<'a> From<&'a Mesh> for Object {
+    fn from(mesh: &'a Mesh) -> Object {
+        mesh.to_object()
+    }
+}
+
+pub struct Triangles<'a> {
+    mesh: &'a Mesh,
+    faces: slice::Iter<'a, Face>,
+}
+
+impl<'a> Iterator for Triangles<'a> {
+    type Item = Triangle;
+
+    fn next(&mut self) -> Option<Triangle> {
+        self.faces.next().map(|face| self.mesh.triangle(face))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.faces.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Triangles<'a> {}
+
+#[cfg(test)]
+mod tests
+{
+    use wavefront::{parse_mesh, parse_object};
+    use wavefront::object_parser::Vertex;
+    use super::{Face, Mesh};
+
+    const CUBE_CORNER: &str = "v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 0.0 0.0 1.0
+vn 0.0 0.0 -1.0
+usemtl Red
+g side
+f 1//1 3//1 2//1
+usemtl Blue
+f 1 2 4
+usemtl Red
+g side bottom
+f 1 4 -2
+g side
+f -4 -1 -2
+";
+
+    #[test]
+    fn parse_mesh_should_share_vertices_and_intern_names() {
+        let mesh = parse_mesh(CUBE_CORNER).unwrap();
+
+        assert_eq!(4, mesh.positions.len());
+        assert_eq!(vec![Vertex{x: 0.0, y: 0.0, z: -1.0}], mesh.normals);
+        assert_eq!(vec!["Red".to_string(), "Blue".to_string()], mesh.materials);
+        assert_eq!(vec![vec!["side".to_string()], vec!["side".to_string(), "bottom".to_string()]], mesh.group_lists);
+
+        assert_eq!(vec![
+            Face{v: [0, 2, 1], vt: [None; 3], vn: [Some(0); 3], material: Some(0), groups: Some(0)},
+            Face{v: [0, 1, 3], vt: [None; 3], vn: [None; 3], material: Some(1), groups: Some(0)},
+            Face{v: [0, 3, 2], vt: [None; 3], vn: [None; 3], material: Some(0), groups: Some(1)},
+            Face{v: [0, 3, 2], vt: [None; 3], vn: [None; 3], material: Some(0), groups: Some(0)},
+        ], mesh.faces);
+
+        assert_eq!(Some(1), mesh.material_id("Blue"));
+        assert_eq!(None, mesh.material_id("Green"));
+    }
+
+    #[test]
+    fn triangles_should_match_parse_object() {
+        let mesh = parse_mesh(CUBE_CORNER).unwrap();
+        let object = parse_object(CUBE_CORNER).unwrap();
+
+        assert_eq!(4, mesh.triangles().len());
+        assert!(mesh.triangles().eq(object.triangles.into_iter()));
+        assert_eq!(parse_object(CUBE_CORNER).unwrap(), mesh.to_object());
+    }
+
+    #[test]
+    fn faces_without_material_or_group_should_have_empty_names() {
+        let mesh = parse_mesh("v 0.0 0.0 0.0
+f 1 1 1
+").unwrap();
+
+        let face = mesh.faces[0];
+        assert_eq!("", mesh.material_name(&face));
+        assert!(mesh.groups(&face).is_empty());
+        assert_eq!(Mesh::default().faces, vec![]);
+    }
+
+    #[test]
+    fn parse_mesh_should_reject_invalid_indices() {
+        assert_eq!(Err("Invalid index".to_string()), parse_mesh("v 1.0 2.0 -3.0
+f 1 2 1
+"));
+        assert_eq!(Err("Invalid index".to_string()), parse_mesh("v 1.0 2.0 -3.0
+f 1//1 1 1
+"));
+    }
+}
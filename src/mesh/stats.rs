@@ -0,0 +1,347 @@
+use std::fmt;
+use geometry::bounds::Aabb;
+use geometry::intersection::{Ray, Triangle};
+use geometry::vector::Vec3;
+use mesh::{HalfEdgeMesh, Mesh};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EdgeLengths {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+/// What's in a `Mesh`, as computed by `Mesh::stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshStats {
+    pub positions: usize,
+    pub texcoords: usize,
+    pub normals: usize,
+    pub faces: usize,
+    pub edges: usize,
+    /// Groups and materials that faces are in, in order of first use.
+    pub groups: Vec<String>,
+    pub materials: Vec<String>,
+    /// The bounds of the vertices used by faces.
+    pub aabb: Option<Aabb<f64>>,
+    pub surface_area: f64,
+    /// Only set for closed meshes, where every edge is shared by two
+    /// consistently wound faces. Each set of faces connected through edges
+    /// is a shell: inward wound shells inside another shell are cavities
+    /// and subtract, other shells count whichever way they are wound.
+    pub volume: Option<f64>,
+    /// Sets of faces connected through shared vertices.
+    pub components: usize,
+    /// V - E + F, counting only the vertices used by faces.
+    pub euler_characteristic: i64,
+    /// Only set for closed meshes without vertices where separate fans of
+    /// faces meet, as the total of their components.
+    pub genus: Option<i64>,
+    pub edge_lengths: Option<EdgeLengths>,
+}
+
+impl Mesh {
+    pub fn stats(&self) -> MeshStats {
+        let half_edges = HalfEdgeMesh::new(self);
+        let point = |v: usize| {
+            let p = self.positions[v];
+            Vec3::new(p.x, p.y, p.z)
+        };
+
+        let mut groups: Vec<String> = Vec::new();
+        let mut materials: Vec<String> = Vec::new();
+        for face in &self.faces {
+            for group in self.groups(face) {
+                if !groups.contains(group) {
+                    groups.push(group.clone());
+                }
+            }
+            if face.material.is_some() && !materials.iter().any(|m| m == self.material_name(face)) {
+                materials.push(self.material_name(face).to_string());
+            }
+        }
+
+        let mut used = vec![false; self.positions.len()];
+        for face in &self.faces {
+            for &v in &face.v {
+                used[v] = true;
+            }
+        }
+        let used_count = used.iter().filter(|&&used| used).count();
+        let aabb = Aabb::from_points((0..self.positions.len()).filter(|&v| used[v]).map(point));
+
+        let solids = solid_of_faces(self, &half_edges);
+        let mut surface_area = 0.0;
+        let mut signed_volumes = vec![0.0; self.faces.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            let (a, b, c) = (point(face.v[0]), point(face.v[1]), point(face.v[2]));
+            surface_area += (b - a).cross_product(c - a).length() / 2.0;
+            signed_volumes[solids[f]] += a.dot_product(b.cross_product(c)) / 6.0;
+        }
+
+        let closed = !self.faces.is_empty() && (0..half_edges.edge_count()).all(|e| {
+            half_edges.edge_half_edges(e).len() == 2 && !half_edges.is_misoriented_edge(e)
+        });
+        let manifold = half_edges.non_manifold_vertices().is_empty();
+
+        let components = count_components(self, &used);
+        let euler_characteristic = used_count as i64 - half_edges.edge_count() as i64 + self.faces.len() as i64;
+
+        let lengths: Vec<f64> = (0..half_edges.edge_count()).map(|e| {
+            let (a, b) = half_edges.edge_vertices(e);
+            point(a).distance(point(b))
+        }).collect();
+        let edge_lengths = if lengths.is_empty() {
+            None
+        } else {
+            Some(EdgeLengths{
+                min: lengths.iter().cloned().fold(f64::INFINITY, f64::min),
+                max: lengths.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+                mean: lengths.iter().sum::<f64>() / lengths.len() as f64,
+            })
+        };
+
+        MeshStats{
+            positions: self.positions.len(),
+            texcoords: self.texcoords.len(),
+            normals: self.normals.len(),
+            faces: self.faces.len(),
+            edges: half_edges.edge_count(),
+            groups,
+            materials,
+            aabb,
+            surface_area,
+            volume: if closed { Some(solid_volume(self, &solids, &signed_volumes)) } else { None },
+            components,
+            euler_characteristic,
+            genus: if closed && manifold { Some(components as i64 - euler_characteristic / 2) } else { None },
+            edge_lengths,
+        }
+    }
+}
+
+fn root(parents: &mut [usize], i: usize) -> usize {
+    let mut i = i;
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+fn count_components(mesh: &Mesh, used: &[bool]) -> usize {
+    let mut parents: Vec<usize> = (0..mesh.positions.len()).collect();
+
+    for face in &mesh.faces {
+        let a = root(&mut parents, face.v[0]);
+        for &v in &face.v[1..] {
+            let b = root(&mut parents, v);
+            parents[b] = a;
+        }
+    }
+
+    (0..parents.len()).filter(|&v| used[v] && root(&mut parents, v) == v).count()
+}
+
+/* For each face, the first face of the set it is connected to through
+   shared edges. Unlike vertex components, solids that only touch at a
+   vertex stay apart. */
+fn solid_of_faces(mesh: &Mesh, half_edges: &HalfEdgeMesh) -> Vec<usize> {
+    let mut parents: Vec<usize> = (0..mesh.faces.len()).collect();
+    for e in 0..half_edges.edge_count() {
+        let faces = half_edges.edge_faces(e);
+        let a = root(&mut parents, faces[0]);
+        for &f in &faces[1..] {
+            let b = root(&mut parents, f);
+            parents[b] = a;
+        }
+    }
+    (0..parents.len()).map(|f| root(&mut parents, f)).collect()
+}
+
+/* Adds up the signed volumes of the shells. An inward wound shell is a
+   cavity when another shell encloses it, and a solid turned inside out
+   when none does. */
+fn solid_volume(mesh: &Mesh, solids: &[usize], signed_volumes: &[f64]) -> f64 {
+    (0..solids.len()).filter(|&f| solids[f] == f).map(|shell| {
+        let volume = signed_volumes[shell];
+        if volume < 0.0 && !is_enclosed(mesh, solids, shell) { -volume } else { volume }
+    }).sum()
+}
+
+/* Whether the centroid of the first face of a shell is inside any other
+   shell, counting crossings along a ray in a direction unlikely to graze
+   edges. */
+fn is_enclosed(mesh: &Mesh, solids: &[usize], shell: usize) -> bool {
+    let triangle = |f: usize| {
+        let p = |i: usize| {
+            let p = mesh.positions[mesh.faces[f].v[i]];
+            Vec3::new(p.x, p.y, p.z)
+        };
+        Triangle::new(p(0), p(1), p(2))
+    };
+    let ray = Ray::new(triangle(shell).centroid(), Vec3::new(0.5257, 0.3090, 0.7925));
+
+    (0..solids.len()).filter(|&f| solids[f] == f && f != shell).any(|other| {
+        let crossings = (0..solids.len())
+            .filter(|&f| solids[f] == other && ray.intersect_triangle(&triangle(f)).is_some())
+            .count();
+        crossings % 2 == 1
+    })
+}
+
+impl fmt::Display for MeshStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let or_none = |names: &[String]| if names.is_empty() { "none".to_string() } else { names.join(", ") };
+
+        writeln!(f, "vertices:       {}", self.positions)?;
+        writeln!(f, "texcoords:      {}", self.texcoords)?;
+        writeln!(f, "normals:        {}", self.normals)?;
+        writeln!(f, "faces:          {}", self.faces)?;
+        writeln!(f, "edges:          {}", self.edges)?;
+        writeln!(f, "groups:         {}", or_none(&self.groups))?;
+        writeln!(f, "materials:      {}", or_none(&self.materials))?;
+        if let Some(ref aabb) = self.aabb {
+            let center = aabb.center();
+            writeln!(f, "bounding box:   ({}, {}, {}) to ({}, {}, {})", aabb.min.x, aabb.min.y, aabb.min.z, aabb.max.x, aabb.max.y, aabb.max.z)?;
+            writeln!(f, "center:         ({}, {}, {})", center.x, center.y, center.z)?;
+        }
+        writeln!(f, "surface area:   {}", self.surface_area)?;
+        match self.volume {
+            Some(volume) => writeln!(f, "volume:         {}", volume)?,
+            None => writeln!(f, "volume:         not closed")?,
+        }
+        writeln!(f, "components:     {}", self.components)?;
+        writeln!(f, "euler:          {}", self.euler_characteristic)?;
+        match self.genus {
+            Some(genus) => writeln!(f, "genus:          {}", genus)?,
+            None if self.volume.is_some() => writeln!(f, "genus:          not a manifold")?,
+            None => writeln!(f, "genus:          not closed")?,
+        }
+        if let Some(ref lengths) = self.edge_lengths {
+            writeln!(f, "edge lengths:   min {}, max {}, mean {}", lengths.min, lengths.max, lengths.mean)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use wavefront::parse_mesh;
+
+    const CUBE: &str = "v 0.0 0.0 0.0
+v 2.0 0.0 0.0
+v 2.0 2.0 0.0
+v 0.0 2.0 0.0
+v 0.0 0.0 2.0
+v 2.0 0.0 2.0
+v 2.0 2.0 2.0
+v 0.0 2.0 2.0
+g cube bottom
+usemtl Grey
+f 1 3 2
+f 1 4 3
+g cube top
+f 5 6 7
+f 5 7 8
+g cube sides
+usemtl Red
+f 1 2 6
+f 1 6 5
+f 2 3 7
+f 2 7 6
+f 3 4 8
+f 3 8 7
+f 4 1 5
+f 4 5 8
+";
+
+    #[test]
+    fn cube_should_be_closed_with_genus_zero() {
+        let stats = parse_mesh(CUBE).unwrap().stats();
+
+        assert_eq!((8, 12, 18), (stats.positions, stats.faces, stats.edges));
+        assert_eq!(vec!["cube", "bottom", "top", "sides"], stats.groups);
+        assert_eq!(vec!["Grey", "Red"], stats.materials);
+        assert_eq!(24.0, stats.surface_area);
+        assert!((stats.volume.unwrap() - 8.0).abs() < 1e-9);
+        assert_eq!((1, 2, Some(0)), (stats.components, stats.euler_characteristic, stats.genus));
+
+        let aabb = stats.aabb.unwrap();
+        assert_eq!((0.0, 2.0), (aabb.min.x, aabb.max.z));
+
+        let lengths = stats.edge_lengths.unwrap();
+        assert_eq!(2.0, lengths.min);
+        assert_eq!(8.0f64.sqrt(), lengths.max);
+    }
+
+    #[test]
+    fn open_mesh_should_have_no_volume_or_genus() {
+        let stats = parse_mesh("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 5.0 5.0 5.0
+f 1 2 3
+").unwrap().stats();
+
+        assert_eq!(0.5, stats.surface_area);
+        assert_eq!((None, None), (stats.volume, stats.genus));
+        assert_eq!((1, 1), (stats.components, stats.euler_characteristic));
+        assert_eq!(1.0, stats.aabb.unwrap().max.x);
+        assert!(stats.groups.is_empty() && stats.materials.is_empty());
+    }
+
+    #[test]
+    fn solids_touching_at_a_vertex_should_have_no_genus_and_keep_their_volumes() {
+        // The second tetrahedron mirrors the first with the same winding,
+        // which turns it inside out, and shares vertex 1 with it.
+        let stats = parse_mesh("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 0.0 0.0 1.0
+v -1.0 0.0 0.0
+v 0.0 -1.0 0.0
+v 0.0 0.0 -1.0
+f 1 3 2
+f 1 2 4
+f 1 4 3
+f 2 3 4
+f 1 6 5
+f 1 5 7
+f 1 7 6
+f 5 6 7
+").unwrap().stats();
+
+        assert_eq!((1, 3, None), (stats.components, stats.euler_characteristic, stats.genus));
+        assert!((stats.volume.unwrap() - 2.0 / 6.0).abs() < 1e-9);
+        assert!(stats.to_string().contains("genus:          not a manifold"));
+    }
+
+    #[test]
+    fn inward_wound_shell_inside_a_cube_should_be_a_cavity() {
+        let inner: String = CUBE.lines().map(|line| {
+            let mut fields = line.split(' ');
+            match fields.next() {
+                Some("v") => format!("v {}\n", fields.map(|c| (c.parse::<f64>().unwrap() / 2.0 + 0.5).to_string())
+                    .collect::<Vec<_>>().join(" ")),
+                Some("f") => format!("f {}\n", fields.rev().map(|i| (i.parse::<usize>().unwrap() + 8).to_string())
+                    .collect::<Vec<_>>().join(" ")),
+                _ => String::new(),
+            }
+        }).collect();
+        let stats = parse_mesh(&(CUBE.to_string() + &inner)).unwrap().stats();
+
+        assert!((stats.volume.unwrap() - 7.0).abs() < 1e-9);
+        assert_eq!((2, Some(0)), (stats.components, stats.genus));
+    }
+
+    #[test]
+    fn empty_mesh_should_have_no_bounds() {
+        let stats = parse_mesh("v 0.0 0.0 0.0\n").unwrap().stats();
+
+        assert_eq!((None, None, None), (stats.aabb, stats.volume, stats.edge_lengths));
+        assert_eq!((0, 0), (stats.components, stats.euler_characteristic));
+        assert!(stats.to_string().contains("volume:         not closed"));
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet};
+use mesh::Mesh;
+use wavefront::object_parser::Vertex;
+
+/// How `Mesh::weld` merges vertices and what it does with the faces that
+/// end up collapsed or repeated.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WeldOptions {
+    /// Positions at most this far apart are merged. Zero, or anything that
+    /// isn't a positive number, only merges positions that are exactly
+    /// equal.
+    pub epsilon: f64,
+    /// Drops faces that have two corners on the same vertex after welding.
+    pub remove_degenerate: bool,
+    /// Drops faces with the same three corners as an earlier face, in any
+    /// order.
+    pub remove_duplicates: bool,
+}
+
+impl Default for WeldOptions {
+    fn default() -> WeldOptions {
+        WeldOptions{epsilon: 1e-6, remove_degenerate: true, remove_duplicates: true}
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct WeldReport {
+    pub merged_vertices: usize,
+    pub degenerate_faces: usize,
+    pub duplicate_faces: usize,
+}
+
+impl Mesh {
+    /// Merges coincident positions and remaps the faces to them. Each merged
+    /// vertex keeps the position of its first occurrence, texture coordinates
+    /// and normals are left alone.
+    pub fn weld(&mut self, options: &WeldOptions) -> WeldReport {
+        let mut report = WeldReport::default();
+        let (positions, remap) = weld_positions(&self.positions, options.epsilon);
+        report.merged_vertices = self.positions.len() - positions.len();
+        self.positions = positions;
+
+        let mut seen = HashSet::new();
+        let faces = self.faces.drain(..).filter_map(|mut face| {
+            for v in face.v.iter_mut() {
+                *v = remap[*v];
+            }
+
+            let [a, b, c] = face.v;
+            if options.remove_degenerate && (a == b || b == c || c == a) {
+                report.degenerate_faces += 1;
+                return None;
+            }
+
+            let mut corners = face.v;
+            corners.sort();
+            if options.remove_duplicates && !seen.insert(corners) {
+                report.duplicate_faces += 1;
+                return None;
+            }
+
+            Some(face)
+        }).collect();
+        self.faces = faces;
+
+        report
+    }
+}
+
+/// Returns the merged positions and, for each original position, the index
+/// of the one it was merged into.
+///
+/// Positions are hashed into cells at least `epsilon` wide, so only the 27
+/// cells around a position have to be searched for one close enough to
+/// merge with. Positions that aren't finite are never merged.
+fn weld_positions(positions: &[Vertex], epsilon: f64) -> (Vec<Vertex>, Vec<usize>) {
+    let mut welded: Vec<Vertex> = Vec::new();
+    let mut remap = Vec::with_capacity(positions.len());
+
+    if epsilon <= 0.0 || epsilon.is_nan() {
+        let mut exact: HashMap<[u64; 3], usize> = HashMap::new();
+        for p in positions {
+            // Adding zero turns -0.0 into 0.0, which would otherwise hash
+            // differently.
+            let key = [(p.x + 0.0).to_bits(), (p.y + 0.0).to_bits(), (p.z + 0.0).to_bits()];
+            let index = *exact.entry(key).or_insert_with(|| {
+                welded.push(*p);
+                welded.len() - 1
+            });
+            remap.push(index);
+        }
+        return (welded, remap);
+    }
+
+    // An epsilon tiny next to the coordinates would give cell numbers too
+    // large for an i64, so cells are kept wide enough to number the bounds
+    // in 2^40 steps.
+    let extent = positions.iter().filter(|p| is_finite(p))
+        .fold(0.0f64, |extent, p| extent.max(p.x.abs()).max(p.y.abs()).max(p.z.abs()));
+    let size = epsilon.max(extent / (1u64 << 40) as f64);
+    let cell = |p: &Vertex| [
+        (p.x / size).floor() as i64,
+        (p.y / size).floor() as i64,
+        (p.z / size).floor() as i64,
+    ];
+    let mut cells: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+
+    for p in positions {
+        if !is_finite(p) {
+            welded.push(*p);
+            remap.push(welded.len() - 1);
+            continue;
+        }
+
+        let [x, y, z] = cell(p);
+        let mut found = None;
+        'search: for dx in -1..2 {
+            for dy in -1..2 {
+                for dz in -1..2 {
+                    if let Some(candidates) = cells.get(&[x + dx, y + dy, z + dz]) {
+                        found = candidates.iter().cloned().find(|&i| distance_squared(&welded[i], p) <= epsilon * epsilon);
+                        if found.is_some() {
+                            break 'search;
+                        }
+                    }
+                }
+            }
+        }
+
+        let index = found.unwrap_or_else(|| {
+            welded.push(*p);
+            cells.entry([x, y, z]).or_default().push(welded.len() - 1);
+            welded.len() - 1
+        });
+        remap.push(index);
+    }
+
+    (welded, remap)
+}
+
+fn is_finite(p: &Vertex) -> bool {
+    p.x.is_finite() && p.y.is_finite() && p.z.is_finite()
+}
+
+fn distance_squared(a: &Vertex, b: &Vertex) -> f64 {
+    let (dx, dy, dz) = (a.x - b.x, a.y - b.y, a.z - b.z);
+    dx * dx + dy * dy + dz * dz
+}
+
+#[cfg(test)]
+mod tests
+{
+    use wavefront::parse_mesh;
+    use super::{WeldOptions, WeldReport};
+
+    const SPLIT_SQUARE: &str = "v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 1.0 0.0
+v 0.0 0.0 -0.0
+v 1.0 1.0000001 0.0
+v 0.0 1.0 0.0
+f 1 2 3
+f 4 5 6
+";
+
+    #[test]
+    fn weld_should_merge_close_positions() {
+        let mut mesh = parse_mesh(SPLIT_SQUARE).unwrap();
+        let report = mesh.weld(&WeldOptions::default());
+
+        assert_eq!(WeldReport{merged_vertices: 2, degenerate_faces: 0, duplicate_faces: 0}, report);
+        assert_eq!(4, mesh.positions.len());
+        assert_eq!([0, 1, 2], mesh.faces[0].v);
+        assert_eq!([0, 2, 3], mesh.faces[1].v);
+    }
+
+    #[test]
+    fn weld_with_zero_epsilon_should_only_merge_equal_positions() {
+        let mut mesh = parse_mesh(SPLIT_SQUARE).unwrap();
+        let report = mesh.weld(&WeldOptions{epsilon: 0.0, ..WeldOptions::default()});
+
+        assert_eq!(1, report.merged_vertices);
+        assert_eq!(5, mesh.positions.len());
+        assert_eq!([0, 3, 4], mesh.faces[1].v);
+    }
+
+    #[test]
+    fn weld_should_merge_across_cell_borders() {
+        let mut mesh = parse_mesh("v 0.0999 0.0 0.0
+v 0.1001 0.0 0.0
+v 0.3 0.0 0.0
+").unwrap();
+        let report = mesh.weld(&WeldOptions{epsilon: 0.1, ..WeldOptions::default()});
+
+        assert_eq!(1, report.merged_vertices);
+        assert_eq!(0.0999, mesh.positions[0].x);
+    }
+
+    #[test]
+    fn weld_should_handle_an_epsilon_tiny_next_to_the_coordinates() {
+        let mut mesh = parse_mesh("v 14.777467 29.361945 27.993464
+v 14.777467 29.361945 27.993464
+v -14.777467 -29.361945 -27.993464
+").unwrap();
+        let report = mesh.weld(&WeldOptions{epsilon: 1e-300, ..WeldOptions::default()});
+
+        assert_eq!(1, report.merged_vertices);
+        assert_eq!(2, mesh.positions.len());
+    }
+
+    #[test]
+    fn weld_should_keep_positions_that_are_not_finite() {
+        let mut mesh = parse_mesh("v 0.0 0.0 0.0
+v 0.0 0.0 0.0
+v 0.0 0.0 0.0
+v 0.0 0.0 0.0
+v 0.0 0.0 0.0
+").unwrap();
+        mesh.positions[1].x = f64::INFINITY;
+        mesh.positions[2].x = f64::INFINITY;
+        mesh.positions[3].x = 1e308;
+        mesh.positions[3].y = f64::NAN;
+        let report = mesh.weld(&WeldOptions{epsilon: 1e-300, ..WeldOptions::default()});
+
+        assert_eq!(1, report.merged_vertices);
+        assert_eq!(4, mesh.positions.len());
+    }
+
+    #[test]
+    fn weld_should_drop_degenerate_and_duplicate_faces() {
+        let source = "v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 1.0 0.0
+v 1.0 0.0 0.0
+f 1 2 3
+f 1 2 4
+f 3 1 4
+f 2 3 1
+";
+        let mut mesh = parse_mesh(source).unwrap();
+        let report = mesh.weld(&WeldOptions::default());
+
+        assert_eq!(WeldReport{merged_vertices: 1, degenerate_faces: 1, duplicate_faces: 2}, report);
+        assert_eq!(1, mesh.faces.len());
+
+        let mut kept = parse_mesh(source).unwrap();
+        let report = kept.weld(&WeldOptions{remove_degenerate: false, remove_duplicates: false, ..WeldOptions::default()});
+
+        assert_eq!(WeldReport{merged_vertices: 1, degenerate_faces: 0, duplicate_faces: 0}, report);
+        assert_eq!(4, kept.faces.len());
+        assert_eq!([0, 1, 1], kept.faces[1].v);
+    }
+}
@@ -0,0 +1,231 @@
+use std::io;
+use std::io::prelude::*;
+use output::{Backend, Drawing, Line, polylines};
+
+/* Layer used for lines whose triangle has no layer name. */
+const DEFAULT_LAYER: &str = "0";
+
+/// Writes the lines as an R12 DXF file, flipping raster y so the drawing is
+/// the right way up in CAD tools. `layers` holds the layer name of each triangle,
+/// indexed by `Line::triangle`; pass an empty slice to put everything on
+/// layer 0.
+///
+/// Consecutive lines of the same triangle that join end to end are written
+/// as a single POLYLINE with its VERTEX entities (closed when the chain
+/// returns to its start), and anything else is written as a LINE.
+pub fn write_dxf<W: Write + ?Sized>(out: &mut W, lines: &[Line], image_height: u32, layers: &[String]) -> io::Result<()> {
+    let layer_of = |triangle: usize| -> String {
+        match layers.get(triangle) {
+            Some(name) if !name.is_empty() => sanitize_layer_name(name),
+            _ => DEFAULT_LAYER.to_string(),
+        }
+    };
+    let flip = |y: f64| image_height as f64 - y;
+
+    let mut layer_names: Vec<String> = vec![DEFAULT_LAYER.to_string()];
+    for line in lines {
+        let name = layer_of(line.triangle);
+        if !layer_names.contains(&name) {
+            layer_names.push(name);
+        }
+    }
+
+    write_header(out, &layer_names)?;
+
+    for polyline in polylines(lines) {
+        let layer = layer_of(polyline.triangle);
+        if polyline.points.len() == 2 && !polyline.closed {
+            let (x1, y1) = polyline.points[0];
+            let (x2, y2) = polyline.points[1];
+            write_group(out, 0, "LINE")?;
+            write_group(out, 8, &layer)?;
+            write_group(out, 10, x1)?;
+            write_group(out, 20, flip(y1))?;
+            write_group(out, 30, 0.0)?;
+            write_group(out, 11, x2)?;
+            write_group(out, 21, flip(y2))?;
+            write_group(out, 31, 0.0)?;
+        } else {
+            /* R12 has no lightweight polylines: the vertices follow as
+               entities of their own, up to the SEQEND. */
+            write_group(out, 0, "POLYLINE")?;
+            write_group(out, 8, &layer)?;
+            write_group(out, 66, 1)?;
+            write_group(out, 10, 0.0)?;
+            write_group(out, 20, 0.0)?;
+            write_group(out, 30, 0.0)?;
+            write_group(out, 70, if polyline.closed { 1 } else { 0 })?;
+            for &(x, y) in &polyline.points {
+                write_group(out, 0, "VERTEX")?;
+                write_group(out, 8, &layer)?;
+                write_group(out, 10, x)?;
+                write_group(out, 20, flip(y))?;
+                write_group(out, 30, 0.0)?;
+            }
+            write_group(out, 0, "SEQEND")?;
+            write_group(out, 8, &layer)?;
+        }
+    }
+
+    write_group(out, 0, "ENDSEC")?;
+    write_group(out, 0, "EOF")
+}
+
+fn write_header<W: Write + ?Sized>(out: &mut W, layer_names: &[String]) -> io::Result<()> {
+    write_group(out, 0, "SECTION")?;
+    write_group(out, 2, "HEADER")?;
+    write_group(out, 9, "$ACADVER")?;
+    write_group(out, 1, "AC1009")?;
+    write_group(out, 0, "ENDSEC")?;
+
+    write_group(out, 0, "SECTION")?;
+    write_group(out, 2, "TABLES")?;
+    write_group(out, 0, "TABLE")?;
+    write_group(out, 2, "LTYPE")?;
+    write_group(out, 70, 1)?;
+    write_group(out, 0, "LTYPE")?;
+    write_group(out, 2, "CONTINUOUS")?;
+    write_group(out, 70, 0)?;
+    write_group(out, 3, "Solid line")?;
+    write_group(out, 72, 65)?;
+    write_group(out, 73, 0)?;
+    write_group(out, 40, 0.0)?;
+    write_group(out, 0, "ENDTAB")?;
+
+    write_group(out, 0, "TABLE")?;
+    write_group(out, 2, "LAYER")?;
+    write_group(out, 70, layer_names.len())?;
+    for name in layer_names {
+        write_group(out, 0, "LAYER")?;
+        write_group(out, 2, name)?;
+        write_group(out, 70, 0)?;
+        write_group(out, 62, 7)?;
+        write_group(out, 6, "CONTINUOUS")?;
+    }
+    write_group(out, 0, "ENDTAB")?;
+    write_group(out, 0, "ENDSEC")?;
+
+    write_group(out, 0, "SECTION")?;
+    write_group(out, 2, "ENTITIES")
+}
+
+fn write_group<W: Write + ?Sized, V: ::std::fmt::Display>(out: &mut W, code: u32, value: V) -> io::Result<()> {
+    writeln!(out, "{}\n{}", code, value)
+}
+
+/* DXF layer names may not contain any of <>/\":;?*|=` */
+fn sanitize_layer_name(name: &str) -> String {
+    name.chars().map(|c| if "<>/\\\":;?*|=`".contains(c) || c.is_control() { '_' } else { c }).collect()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Layers {
+    /* One layer per material. */
+    Material,
+    /* One layer per group, using the first group of each face. */
+    Group,
+    /* Everything on layer 0. */
+    None,
+}
+
+pub struct Dxf {
+    pub layers: Layers,
+}
+
+impl Backend for Dxf {
+    fn write(&self, drawing: &Drawing, out: &mut dyn Write) -> io::Result<()> {
+        let source = &drawing.source;
+        let triangles = 0..source.triangle_count();
+        let layers: Vec<String> = match self.layers {
+            Layers::Material => triangles.map(|t| source.material_name(t).to_string()).collect(),
+            Layers::Group => triangles.map(|t| source.groups(t).first().cloned().unwrap_or_default()).collect(),
+            Layers::None => vec![],
+        };
+        write_dxf(out, &drawing.lines, drawing.image_height, &layers)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use output::Line;
+    use super::{write_dxf, sanitize_layer_name};
+
+    fn entities(dxf: &str) -> Vec<String> {
+        let start = dxf.find("ENTITIES\n").unwrap() + "ENTITIES\n".len();
+        dxf[start..].lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn write_dxf_should_write_closed_triangles_as_polylines() {
+        let lines = vec![
+            Line::new(0.0, 0.0, 4.0, 0.0, 0),
+            Line::new(4.0, 0.0, 4.0, 3.0, 0),
+            Line::new(4.0, 3.0, 0.0, 0.0, 0),
+        ];
+        let mut out: Vec<u8> = Vec::new();
+        write_dxf(&mut out, &lines, 10, &["Leaves".to_string()]).unwrap();
+        let dxf = String::from_utf8(out).unwrap();
+
+        assert!(dxf.contains("0\nLAYER\n2\nLeaves\n"));
+        assert_eq!(vec!["0", "POLYLINE", "8", "Leaves", "66", "1", "10", "0", "20", "0", "30", "0", "70", "1",
+                        "0", "VERTEX", "8", "Leaves", "10", "0", "20", "10", "30", "0",
+                        "0", "VERTEX", "8", "Leaves", "10", "4", "20", "10", "30", "0",
+                        "0", "VERTEX", "8", "Leaves", "10", "4", "20", "7", "30", "0",
+                        "0", "SEQEND", "8", "Leaves",
+                        "0", "ENDSEC", "0", "EOF"], entities(&dxf));
+    }
+
+    #[test]
+    fn write_dxf_should_write_unconnected_segments_as_lines() {
+        let lines = vec![
+            Line::new(0.0, 0.0, 4.0, 0.0, 0),
+            Line::new(1.0, 1.0, 2.0, 2.0, 1),
+        ];
+        let mut out: Vec<u8> = Vec::new();
+        write_dxf(&mut out, &lines, 10, &[]).unwrap();
+        let dxf = String::from_utf8(out).unwrap();
+
+        assert_eq!(vec!["0", "LINE", "8", "0", "10", "0", "20", "10", "30", "0", "11", "4", "21", "10", "31", "0",
+                        "0", "LINE", "8", "0", "10", "1", "20", "9", "30", "0", "11", "2", "21", "8", "31", "0",
+                        "0", "ENDSEC", "0", "EOF"], entities(&dxf));
+    }
+
+    #[test]
+    fn write_dxf_should_write_open_chains_with_their_end_point() {
+        let lines = vec![
+            Line::new(0.0, 0.0, 1.0, 0.0, 2),
+            Line::new(1.0, 0.0, 1.0, 1.0, 2),
+        ];
+        let mut out: Vec<u8> = Vec::new();
+        write_dxf(&mut out, &lines, 0, &[]).unwrap();
+        let dxf = String::from_utf8(out).unwrap();
+
+        assert_eq!(vec!["0", "POLYLINE", "8", "0", "66", "1", "10", "0", "20", "0", "30", "0", "70", "0",
+                        "0", "VERTEX", "8", "0", "10", "0", "20", "0", "30", "0",
+                        "0", "VERTEX", "8", "0", "10", "1", "20", "0", "30", "0",
+                        "0", "VERTEX", "8", "0", "10", "1", "20", "-1", "30", "0",
+                        "0", "SEQEND", "8", "0",
+                        "0", "ENDSEC", "0", "EOF"], entities(&dxf));
+    }
+
+    #[test]
+    fn write_dxf_should_write_an_r12_header_and_the_linetype_its_layers_use() {
+        let mut out: Vec<u8> = Vec::new();
+        write_dxf(&mut out, &[Line::new(0.0, 0.0, 1.0, 0.0, 0)], 0, &["Bark".to_string()]).unwrap();
+        let dxf = String::from_utf8(out).unwrap();
+
+        assert!(dxf.starts_with("0\nSECTION\n2\nHEADER\n9\n$ACADVER\n1\nAC1009\n0\nENDSEC\n"));
+        let ltype = dxf.find("0\nTABLE\n2\nLTYPE\n70\n1\n0\nLTYPE\n2\nCONTINUOUS\n").unwrap();
+        let layers = dxf.find("0\nTABLE\n2\nLAYER\n70\n2\n").unwrap();
+        assert!(ltype < layers);
+        assert_eq!(2, dxf.matches("62\n7\n6\nCONTINUOUS\n").count());
+        assert!(!dxf.contains("LWPOLYLINE"));
+    }
+
+    #[test]
+    fn sanitize_layer_name_should_replace_reserved_characters() {
+        assert_eq!("Material.01", sanitize_layer_name("Material.01"));
+        assert_eq!("a_b_c_", sanitize_layer_name("a/b:c*"));
+    }
+}
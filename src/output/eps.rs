@@ -0,0 +1,86 @@
+use std::io;
+use std::io::prelude::*;
+use output::{Backend, Drawing, Line, Rgb};
+use output::page::Page;
+
+pub fn write_eps<W: Write + ?Sized>(out: &mut W, lines: &[Line], image_width: u32, image_height: u32, page: &Page, stroke_width: f64, background: Option<Rgb>) -> io::Result<()> {
+    let placement = page.place(image_width, image_height);
+
+    writeln!(out, "%!PS-Adobe-3.0 EPSF-3.0")?;
+    writeln!(out, "%%BoundingBox: 0 0 {} {}", page.width.ceil() as i64, page.height.ceil() as i64)?;
+    writeln!(out, "%%HiResBoundingBox: 0 0 {:.3} {:.3}", page.width, page.height)?;
+    writeln!(out, "%%Creator: sticks")?;
+    writeln!(out, "%%Pages: 1")?;
+    writeln!(out, "%%EndComments")?;
+    writeln!(out, "gsave")?;
+    if let Some(color) = background {
+        let (r, g, b) = color.fractions();
+        let (x, y) = placement.map(0.0, image_height as f64);
+        let (width, height) = (image_width as f64 * placement.scale, image_height as f64 * placement.scale);
+        writeln!(out, "{:.3} {:.3} {:.3} setrgbcolor {:.3} {:.3} {:.3} {:.3} rectfill", r, g, b, x, y, width, height)?;
+    }
+    writeln!(out, "{:.3} setlinewidth 1 setlinecap 1 setlinejoin 0 setgray", stroke_width)?;
+    writeln!(out, "newpath")?;
+
+    for line in lines.iter().filter(|line| line.is_finite()) {
+        let (x1, y1) = placement.map(line.x1, line.y1);
+        let (x2, y2) = placement.map(line.x2, line.y2);
+        writeln!(out, "{:.3} {:.3} moveto {:.3} {:.3} lineto", x1, y1, x2, y2)?;
+    }
+
+    writeln!(out, "stroke")?;
+    writeln!(out, "grestore")?;
+    writeln!(out, "showpage")?;
+    writeln!(out, "%%EOF")
+}
+
+pub struct Eps {
+    pub page: Page,
+    pub stroke_width: f64,
+}
+
+impl Backend for Eps {
+    fn write(&self, drawing: &Drawing, out: &mut dyn Write) -> io::Result<()> {
+        write_eps(out, &drawing.lines, drawing.image_width, drawing.image_height, &self.page, self.stroke_width, drawing.background)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use output::Line;
+    use output::page::Page;
+    use super::write_eps;
+
+    #[test]
+    fn write_eps_should_write_a_bounding_box_and_a_path_per_line() {
+        let mut out: Vec<u8> = Vec::new();
+        write_eps(&mut out, &[Line::new(0.0, 0.0, 10.0, 10.0, 0)], 10, 10, &Page::new(100.0, 50.0), 0.5, None).unwrap();
+
+        let expected = "%!PS-Adobe-3.0 EPSF-3.0
+%%BoundingBox: 0 0 100 50
+%%HiResBoundingBox: 0 0 100.000 50.000
+%%Creator: sticks
+%%Pages: 1
+%%EndComments
+gsave
+0.500 setlinewidth 1 setlinecap 1 setlinejoin 0 setgray
+newpath
+25.000 50.000 moveto 75.000 0.000 lineto
+stroke
+grestore
+showpage
+%%EOF
+";
+        assert_eq!(expected, String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn write_eps_should_leave_out_lines_that_are_not_finite() {
+        let lines = [Line::new(f64::NAN, 0.0, 10.0, 10.0, 0), Line::new(0.0, 0.0, 10.0, f64::NEG_INFINITY, 0)];
+        let mut out: Vec<u8> = Vec::new();
+        write_eps(&mut out, &lines, 10, 10, &Page::new(100.0, 50.0), 0.5, None).unwrap();
+
+        assert!(String::from_utf8(out).unwrap().contains("newpath\nstroke\n"));
+    }
+}
@@ -0,0 +1,249 @@
+pub mod dxf;
+pub mod eps;
+pub mod page;
+pub mod pdf;
+pub mod svg;
+pub mod terminal;
+pub mod tikz;
+
+use std::io;
+use std::io::prelude::*;
+use wavefront::material_parser::Material;
+use mesh::Mesh;
+use wavefront::object_parser::{Object, Vertex};
+
+/// An edge of the wireframe, projected into raster space. `triangle` is the
+/// index of the triangle in the `Source` that the edge came from, so that
+/// backends can look up its material or groups.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Line {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+    pub triangle: usize,
+}
+
+impl Line {
+    pub fn new(x1: f64, y1: f64, x2: f64, y2: f64, triangle: usize) -> Line {
+        Line{x1, y1, x2, y2, triangle}
+    }
+
+    /// Whether both ends have finite coordinates. Backends leave out lines
+    /// that don't, such as ones through a vertex with NaN coordinates.
+    pub fn is_finite(&self) -> bool {
+        self.x1.is_finite() && self.y1.is_finite() && self.x2.is_finite() && self.y2.is_finite()
+    }
+}
+
+/// A color with 8 bits per channel.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb{r, g, b}
+    }
+
+    /// Parses `white`, `black` or a hex color such as `#ff8000` or `#f80`.
+    pub fn parse(string: &str) -> Result<Rgb, String> {
+        let error = || format!("Invalid color: {}. Expected white, black or a hex color such as #ff8000.", string);
+        let hex = match string.trim().to_lowercase().as_str() {
+            "white" => return Ok(Rgb::new(255, 255, 255)),
+            "black" => return Ok(Rgb::new(0, 0, 0)),
+            other => other.trim_start_matches('#').to_string(),
+        };
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+
+        let channel = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| error());
+        match hex.len() {
+            3 => {
+                let c: Vec<String> = hex.chars().map(|c| format!("{}{}", c, c)).collect();
+                Ok(Rgb::new(channel(&c[0])?, channel(&c[1])?, channel(&c[2])?))
+            },
+            6 => Ok(Rgb::new(channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?)),
+            _ => Err(error()),
+        }
+    }
+
+    /// The channels as fractions between 0 and 1, as PDF and PostScript
+    /// take them.
+    pub fn fractions(&self) -> (f64, f64, f64) {
+        (self.r as f64 / 255.0, self.g as f64 / 255.0, self.b as f64 / 255.0)
+    }
+}
+
+/// What a drawing is rendered from. A `Mesh` is rendered straight from its
+/// shared vertices, without building the triangles of an `Object`.
+#[derive(Debug, Copy, Clone)]
+pub enum Source<'a> {
+    Object(&'a Object),
+    Mesh(&'a Mesh),
+}
+
+impl<'a> Source<'a> {
+    pub fn triangle_count(&self) -> usize {
+        match *self {
+            Source::Object(object) => object.triangles.len(),
+            Source::Mesh(mesh) => mesh.faces.len(),
+        }
+    }
+
+    pub fn corners(&self, triangle: usize) -> [Vertex; 3] {
+        match *self {
+            Source::Object(object) => {
+                let t = &object.triangles[triangle];
+                [t.v1, t.v2, t.v3]
+            },
+            Source::Mesh(mesh) => {
+                let [a, b, c] = mesh.faces[triangle].v;
+                [mesh.positions[a], mesh.positions[b], mesh.positions[c]]
+            },
+        }
+    }
+
+    /// The triangle's material name, or `""` when it has none.
+    pub fn material_name(&self, triangle: usize) -> &'a str {
+        match *self {
+            Source::Object(object) => &object.triangles[triangle].materialName,
+            Source::Mesh(mesh) => mesh.material_name(&mesh.faces[triangle]),
+        }
+    }
+
+    pub fn groups(&self, triangle: usize) -> &'a [String] {
+        match *self {
+            Source::Object(object) => object.groups(&object.triangles[triangle]),
+            Source::Mesh(mesh) => mesh.groups(&mesh.faces[triangle]),
+        }
+    }
+}
+
+impl<'a> From<&'a Object> for Source<'a> {
+    fn from(object: &'a Object) -> Source<'a> {
+        Source::Object(object)
+    }
+}
+
+impl<'a> From<&'a Mesh> for Source<'a> {
+    fn from(mesh: &'a Mesh) -> Source<'a> {
+        Source::Mesh(mesh)
+    }
+}
+
+/// Everything a backend needs to write out a rendered object.
+pub struct Drawing<'a> {
+    pub lines: Vec<Line>,
+    pub image_width: u32,
+    pub image_height: u32,
+    /// The color to fill the image with before drawing, if any. Backends
+    /// that can't fill an area leave it out.
+    pub background: Option<Rgb>,
+    pub source: Source<'a>,
+    pub materials: &'a [Material],
+}
+
+impl<'a> Drawing<'a> {
+    /// The material of each triangle of the source, looked up by name.
+    pub fn triangle_materials(&self) -> Vec<Option<&'a Material>> {
+        let materials = self.materials;
+        (0..self.source.triangle_count())
+            .map(|t| materials.iter().find(|m| m.name == self.source.material_name(t)))
+            .collect()
+    }
+}
+
+/// An output format that a `Drawing` can be written in.
+pub trait Backend {
+    fn write(&self, drawing: &Drawing, out: &mut dyn Write) -> io::Result<()>;
+}
+
+/// A run of consecutive lines from the same triangle that join end to end.
+/// When `closed` is set the last point connects back to the first one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<(f64, f64)>,
+    pub closed: bool,
+    pub triangle: usize,
+}
+
+pub fn polylines(lines: &[Line]) -> Vec<Polyline> {
+    let lines: Vec<&Line> = lines.iter().filter(|line| line.is_finite()).collect();
+    let mut polylines = Vec::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        let first = &lines[i];
+        let mut last = first;
+        let mut j = i + 1;
+        while j < lines.len() && lines[j].triangle == first.triangle && lines[j].x1 == last.x2 && lines[j].y1 == last.y2 {
+            last = &lines[j];
+            j += 1;
+        }
+
+        /* A single segment is never closed, even if it is degenerate. */
+        let closed = j - i > 1 && last.x2 == first.x1 && last.y2 == first.y1;
+        let mut points: Vec<(f64, f64)> = lines[i..j].iter().map(|line| (line.x1, line.y1)).collect();
+        if !closed {
+            points.push((last.x2, last.y2));
+        }
+
+        polylines.push(Polyline{points, closed, triangle: first.triangle});
+        i = j;
+    }
+
+    polylines
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{Line, Polyline, Rgb, polylines};
+
+    #[test]
+    fn rgb_should_parse_names_and_hex_colors() {
+        assert_eq!(Ok(Rgb::new(255, 255, 255)), Rgb::parse("White"));
+        assert_eq!(Ok(Rgb::new(255, 128, 0)), Rgb::parse("#ff8000"));
+        assert_eq!(Ok(Rgb::new(255, 136, 0)), Rgb::parse("f80"));
+        assert!(Rgb::parse("#ff80").is_err());
+        assert!(Rgb::parse("#gg8000").is_err());
+        assert!(Rgb::parse("#é8000").is_err());
+    }
+
+    #[test]
+    fn polylines_should_join_connected_lines_of_the_same_triangle() {
+        let lines = vec![
+            Line::new(0.0, 0.0, 1.0, 0.0, 0),
+            Line::new(1.0, 0.0, 1.0, 1.0, 0),
+            Line::new(1.0, 1.0, 0.0, 0.0, 0),
+            Line::new(0.0, 0.0, 1.0, 0.0, 1),
+            Line::new(1.0, 0.0, 1.0, 1.0, 1),
+            Line::new(1.0, 1.0, 2.0, 2.0, 2),
+            Line::new(5.0, 5.0, 6.0, 6.0, 2),
+        ];
+
+        assert_eq!(vec![
+            Polyline{points: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)], closed: true, triangle: 0},
+            Polyline{points: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)], closed: false, triangle: 1},
+            Polyline{points: vec![(1.0, 1.0), (2.0, 2.0)], closed: false, triangle: 2},
+            Polyline{points: vec![(5.0, 5.0), (6.0, 6.0)], closed: false, triangle: 2},
+        ], polylines(&lines));
+    }
+
+    #[test]
+    fn polylines_should_leave_out_lines_that_are_not_finite() {
+        let lines = vec![
+            Line::new(0.0, 0.0, 1.0, 0.0, 0),
+            Line::new(1.0, 0.0, f64::NAN, 1.0, 0),
+            Line::new(f64::NAN, 1.0, 0.0, 0.0, 0),
+            Line::new(f64::INFINITY, 0.0, 1.0, 0.0, 1),
+        ];
+
+        assert_eq!(vec![Polyline{points: vec![(0.0, 0.0), (1.0, 0.0)], closed: false, triangle: 0}], polylines(&lines));
+    }
+}
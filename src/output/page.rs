@@ -0,0 +1,141 @@
+/* Physical lengths are kept in PostScript points, which is the unit both
+   PDF and EPS use for their coordinate systems. */
+pub const POINTS_PER_INCH: f64 = 72.0;
+pub const POINTS_PER_MM: f64 = 72.0 / 25.4;
+
+/// Parses a length such as `0.25mm`, `1cm`, `0.01in` or `2pt` into points.
+pub fn parse_length(string: &str) -> Result<f64, String> {
+    let string = string.trim();
+    let units: [(&str, f64); 4] = [("mm", POINTS_PER_MM), ("cm", POINTS_PER_MM * 10.0), ("in", POINTS_PER_INCH), ("pt", 1.0)];
+
+    for &(suffix, factor) in units.iter() {
+        if let Some(number) = string.strip_suffix(suffix) {
+            return match number.trim().parse::<f64>() {
+                Ok(value) if value >= 0.0 => Ok(value * factor),
+                _ => Err(format!("Invalid length: {}", string)),
+            };
+        }
+    }
+
+    Err(format!("Invalid length: {}. Expected a number followed by mm, cm, in or pt.", string))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Page {
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Where the raster image ends up on the page: raster pixels are scaled
+/// uniformly and centered, with the y axis flipped to point up.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Placement {
+    pub scale: f64,
+    pub offset_x: f64,
+    pub offset_y: f64,
+    pub image_height: f64,
+}
+
+impl Page {
+    pub fn new(width: f64, height: f64) -> Page {
+        Page{width, height}
+    }
+
+    /// Parses `a3`, `a4`, `a5`, `letter`, `legal` or a custom size such as
+    /// `100x150mm` or `8.5x11in`.
+    pub fn parse(string: &str) -> Result<Page, String> {
+        match string.trim().to_lowercase().as_str() {
+            "a3" => Ok(Page::new(297.0 * POINTS_PER_MM, 420.0 * POINTS_PER_MM)),
+            "a4" => Ok(Page::new(210.0 * POINTS_PER_MM, 297.0 * POINTS_PER_MM)),
+            "a5" => Ok(Page::new(148.0 * POINTS_PER_MM, 210.0 * POINTS_PER_MM)),
+            "letter" => Ok(Page::new(8.5 * POINTS_PER_INCH, 11.0 * POINTS_PER_INCH)),
+            "legal" => Ok(Page::new(8.5 * POINTS_PER_INCH, 14.0 * POINTS_PER_INCH)),
+            custom => {
+                let error = format!("Invalid page size: {}. Expected a4, letter or a size such as 100x150mm.", string);
+                let mut parts = custom.splitn(2, 'x');
+                let width = parts.next().unwrap_or("");
+                let height = match parts.next() {
+                    Some(h) => h,
+                    None => return Err(error),
+                };
+
+                /* The unit is only required once, at the end: 100x150mm. */
+                let unit: String = height.chars().skip_while(|c| c.is_ascii_digit() || *c == '.').collect();
+                let width = if width.chars().all(|c| c.is_ascii_digit() || c == '.') {
+                    format!("{}{}", width, unit)
+                } else {
+                    width.to_string()
+                };
+
+                match (parse_length(&width), parse_length(height)) {
+                    (Ok(w), Ok(h)) if w > 0.0 && h > 0.0 => Ok(Page::new(w, h)),
+                    _ => Err(error),
+                }
+            },
+        }
+    }
+
+    pub fn place(&self, image_width: u32, image_height: u32) -> Placement {
+        let image_width = image_width as f64;
+        let image_height = image_height as f64;
+        let scale = (self.width / image_width).min(self.height / image_height);
+
+        Placement{
+            scale,
+            offset_x: (self.width - image_width * scale) * 0.5,
+            offset_y: (self.height - image_height * scale) * 0.5,
+            image_height,
+        }
+    }
+}
+
+impl Placement {
+    /// Maps a raster position onto the page, in points from the bottom left corner.
+    pub fn map(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.offset_x + x * self.scale, self.offset_y + (self.image_height - y) * self.scale)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{Page, parse_length, POINTS_PER_MM};
+
+    #[test]
+    fn parse_length_should_convert_units_to_points() {
+        assert_delta!(parse_length("25.4mm").unwrap(), 72.0, 0.0001);
+        assert_delta!(parse_length("2.54cm").unwrap(), 72.0, 0.0001);
+        assert_delta!(parse_length("0.5in").unwrap(), 36.0, 0.0001);
+        assert_delta!(parse_length(" 3pt ").unwrap(), 3.0, 0.0001);
+        assert!(parse_length("3").is_err());
+        assert!(parse_length("mm").is_err());
+        assert!(parse_length("-1mm").is_err());
+    }
+
+    #[test]
+    fn parse_should_parse_named_and_custom_page_sizes() {
+        let a4 = Page::parse("A4").unwrap();
+        assert_delta!(a4.width, 595.2756, 0.001);
+        assert_delta!(a4.height, 841.8898, 0.001);
+
+        assert_eq!(Ok(Page::new(612.0, 792.0)), Page::parse("letter"));
+        assert_eq!(Ok(Page::new(612.0, 792.0)), Page::parse("8.5x11in"));
+        assert_eq!(Ok(Page::new(612.0, 72.0)), Page::parse("8.5inx25.4mm"));
+
+        let custom = Page::parse("100x150mm").unwrap();
+        assert_delta!(custom.width, 100.0 * POINTS_PER_MM, 0.0001);
+        assert_delta!(custom.height, 150.0 * POINTS_PER_MM, 0.0001);
+
+        assert!(Page::parse("b7").is_err());
+        assert!(Page::parse("100mm").is_err());
+        assert!(Page::parse("0x100mm").is_err());
+    }
+
+    #[test]
+    fn place_should_center_and_flip_the_image() {
+        let placement = Page::new(200.0, 100.0).place(50, 50);
+        assert_delta!(placement.scale, 2.0, 0.0001);
+        assert_eq!((50.0, 100.0), placement.map(0.0, 0.0));
+        assert_eq!((150.0, 0.0), placement.map(50.0, 50.0));
+    }
+}
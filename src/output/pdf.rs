@@ -0,0 +1,116 @@
+use std::io;
+use std::io::prelude::*;
+use output::{Backend, Drawing, Line, Rgb};
+use output::page::Page;
+
+/* Builds an uncompressed, single page PDF. The document is assembled in
+   memory because the cross-reference table needs the byte offset of every
+   object. */
+pub fn write_pdf<W: Write + ?Sized>(out: &mut W, lines: &[Line], image_width: u32, image_height: u32, page: &Page, stroke_width: f64, background: Option<Rgb>) -> io::Result<()> {
+    let placement = page.place(image_width, image_height);
+
+    let mut content = String::new();
+    if let Some(color) = background {
+        let (r, g, b) = color.fractions();
+        let (x, y) = placement.map(0.0, image_height as f64);
+        let (width, height) = (image_width as f64 * placement.scale, image_height as f64 * placement.scale);
+        content.push_str(&format!("{:.3} {:.3} {:.3} rg {:.3} {:.3} {:.3} {:.3} re f\n", r, g, b, x, y, width, height));
+    }
+    content.push_str(&format!("{:.3} w 1 J 1 j 0 G\n", stroke_width));
+    for line in lines.iter().filter(|line| line.is_finite()) {
+        let (x1, y1) = placement.map(line.x1, line.y1);
+        let (x2, y2) = placement.map(line.x2, line.y2);
+        content.push_str(&format!("{:.3} {:.3} m {:.3} {:.3} l\n", x1, y1, x2, y2));
+    }
+    content.push('S');
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.3} {:.3}] /Contents 4 0 R /Resources << >> >>", page.width, page.height),
+        format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+    ];
+
+    let mut document: Vec<u8> = Vec::new();
+    let mut offsets = Vec::with_capacity(objects.len());
+    document.extend_from_slice(b"%PDF-1.4\n");
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(document.len());
+        document.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+
+    let xref_offset = document.len();
+    document.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        document.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    document.extend_from_slice(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref_offset).as_bytes());
+
+    out.write_all(&document)
+}
+
+pub struct Pdf {
+    pub page: Page,
+    pub stroke_width: f64,
+}
+
+impl Backend for Pdf {
+    fn write(&self, drawing: &Drawing, out: &mut dyn Write) -> io::Result<()> {
+        write_pdf(out, &drawing.lines, drawing.image_width, drawing.image_height, &self.page, self.stroke_width, drawing.background)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use output::{Line, Rgb};
+    use output::page::Page;
+    use super::write_pdf;
+
+    #[test]
+    fn write_pdf_should_write_a_page_with_a_stroked_path() {
+        let mut out: Vec<u8> = Vec::new();
+        write_pdf(&mut out, &[Line::new(0.0, 0.0, 10.0, 10.0, 0)], 10, 10, &Page::new(100.0, 50.0), 0.5, None).unwrap();
+        let pdf = String::from_utf8(out).unwrap();
+
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/MediaBox [0 0 100.000 50.000]"));
+        assert!(pdf.contains("0.500 w 1 J 1 j 0 G\n25.000 50.000 m 75.000 0.000 l\nS\n"));
+    }
+
+    #[test]
+    fn write_pdf_should_leave_out_lines_that_are_not_finite() {
+        let lines = [Line::new(f64::NAN, 0.0, 10.0, 10.0, 0), Line::new(0.0, 0.0, 10.0, f64::INFINITY, 0)];
+        let mut out: Vec<u8> = Vec::new();
+        write_pdf(&mut out, &lines, 10, 10, &Page::new(100.0, 50.0), 0.5, None).unwrap();
+        let pdf = String::from_utf8(out).unwrap();
+
+        assert!(pdf.contains("0.500 w 1 J 1 j 0 G\nS\n"));
+    }
+
+    #[test]
+    fn write_pdf_should_fill_the_image_with_the_background() {
+        let mut out: Vec<u8> = Vec::new();
+        write_pdf(&mut out, &[Line::new(0.0, 0.0, 10.0, 10.0, 0)], 10, 10, &Page::new(100.0, 50.0), 0.5, Some(Rgb::new(255, 0, 0))).unwrap();
+        let pdf = String::from_utf8(out).unwrap();
+
+        assert!(pdf.contains("1.000 0.000 0.000 rg 25.000 0.000 50.000 50.000 re f\n0.500 w"));
+    }
+
+    #[test]
+    fn write_pdf_should_point_the_xref_table_at_each_object() {
+        let mut out: Vec<u8> = Vec::new();
+        write_pdf(&mut out, &[Line::new(0.0, 0.0, 10.0, 10.0, 0)], 10, 10, &Page::new(100.0, 50.0), 0.5, None).unwrap();
+        let pdf = String::from_utf8(out).unwrap();
+
+        let startxref: usize = pdf.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert!(pdf[startxref..].starts_with("xref\n0 5\n"));
+
+        let entries: Vec<&str> = pdf[startxref..].lines().skip(3).take(4).collect();
+        for (i, entry) in entries.iter().enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::io;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use output::{Backend, Drawing, Line, Rgb};
+
+/// How the lines of a group are stroked.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stroke {
+    pub color: Rgb,
+    pub width: f64,
+}
+
+impl Default for Stroke {
+    fn default() -> Stroke {
+        Stroke{color: Rgb::new(0, 0, 0), width: 1.0}
+    }
+}
+
+/// Writes an SVG document element by element through a `BufWriter`.
+///
+/// Lines go into `<g>` elements that carry their stroke, and a new group is
+/// only started when the stroke changes. `finish` has to be called to close
+/// the document and flush it, dropping the writer loses any write error.
+pub struct SvgWriter<W: Write> {
+    out: BufWriter<W>,
+    image_width: u32,
+    image_height: u32,
+    stroke: Option<Stroke>,
+}
+
+impl<W: Write> SvgWriter<W> {
+    /// Starts a document of `image_width` by `image_height` pixels, with a
+    /// view box of the same size so that lines are given in raster space.
+    pub fn new(out: W, image_width: u32, image_height: u32) -> io::Result<SvgWriter<W>> {
+        let mut out = BufWriter::new(out);
+        writeln!(out, "<svg version=\"1.1\" xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">", image_width, image_height)?;
+        Ok(SvgWriter{out, image_width, image_height, stroke: None})
+    }
+
+    /// Fills the whole image, covering whatever was written before.
+    pub fn fill(&mut self, color: Rgb) -> io::Result<()> {
+        self.end_group()?;
+        writeln!(self.out, "<rect width=\"{}\" height=\"{}\" fill=\"rgb({},{},{})\" />", self.image_width, self.image_height, color.r, color.g, color.b)
+    }
+
+    /// Lines with coordinates that aren't finite are left out.
+    pub fn line(&mut self, line: &Line, stroke: Stroke) -> io::Result<()> {
+        if !line.is_finite() {
+            return Ok(());
+        }
+        if self.stroke != Some(stroke) {
+            self.end_group()?;
+            let color = stroke.color;
+            writeln!(self.out, "<g fill=\"none\" stroke=\"rgb({},{},{})\" stroke-width=\"{}\">", color.r, color.g, color.b, stroke.width)?;
+            self.stroke = Some(stroke);
+        }
+        writeln!(self.out, "<line x1=\"{:.3}\" y1=\"{:.3}\" x2=\"{:.3}\" y2=\"{:.3}\" />", line.x1, line.y1, line.x2, line.y2)
+    }
+
+    fn end_group(&mut self) -> io::Result<()> {
+        if self.stroke.take().is_some() {
+            writeln!(self.out, "</g>")?;
+        }
+        Ok(())
+    }
+
+    /// Closes the document, flushes it and hands back the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.end_group()?;
+        writeln!(self.out, "</svg>")?;
+        self.out.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+pub fn write_svg<W: Write + ?Sized>(out: &mut W, lines: &[Line], image_width: u32, image_height: u32, background: Option<Rgb>) -> io::Result<()> {
+    let mut svg = SvgWriter::new(out, image_width, image_height)?;
+    if let Some(color) = background {
+        svg.fill(color)?;
+    }
+    for line in lines {
+        svg.line(line, Stroke::default())?;
+    }
+    svg.finish()?;
+    Ok(())
+}
+
+pub struct Svg;
+
+impl Backend for Svg {
+    fn write(&self, drawing: &Drawing, out: &mut dyn Write) -> io::Result<()> {
+        write_svg(out, &drawing.lines, drawing.image_width, drawing.image_height, drawing.background)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::io;
+    use std::io::prelude::*;
+    use output::{Line, Rgb};
+    use super::{write_svg, Stroke, SvgWriter};
+
+    #[test]
+    fn write_svg_should_write_a_line_element_per_line() {
+        let lines = vec![Line::new(1.0, 2.0, 3.0, 4.0, 0), Line::new(3.7, 4.2, 1.0, 2.0, 0)];
+        let mut out: Vec<u8> = Vec::new();
+        write_svg(&mut out, &lines, 512, 256, None).unwrap();
+
+        let expected = "<svg version=\"1.1\" xmlns=\"http://www.w3.org/2000/svg\" width=\"512\" height=\"256\" viewBox=\"0 0 512 256\">
+<g fill=\"none\" stroke=\"rgb(0,0,0)\" stroke-width=\"1\">
+<line x1=\"1.000\" y1=\"2.000\" x2=\"3.000\" y2=\"4.000\" />
+<line x1=\"3.700\" y1=\"4.200\" x2=\"1.000\" y2=\"2.000\" />
+</g>
+</svg>
+";
+        assert_eq!(expected, String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn svg_writer_should_start_a_group_when_the_stroke_changes() {
+        let red = Stroke{color: Rgb::new(255, 0, 0), width: 0.5};
+        let mut svg = SvgWriter::new(Vec::new(), 10, 10).unwrap();
+        svg.line(&Line::new(0.0, 0.0, 1.0, 1.0, 0), red).unwrap();
+        svg.line(&Line::new(1.0, 1.0, 2.0, 2.0, 0), red).unwrap();
+        svg.line(&Line::new(2.0, 2.0, 3.0, 3.0, 1), Stroke::default()).unwrap();
+        let out = String::from_utf8(svg.finish().unwrap()).unwrap();
+
+        assert_eq!(2, out.matches("<g ").count());
+        assert_eq!(2, out.matches("</g>").count());
+        assert!(out.contains("<g fill=\"none\" stroke=\"rgb(255,0,0)\" stroke-width=\"0.5\">"));
+    }
+
+    #[test]
+    fn svg_writer_should_leave_out_lines_that_are_not_finite() {
+        let mut svg = SvgWriter::new(Vec::new(), 10, 10).unwrap();
+        svg.line(&Line::new(f64::NAN, 0.0, 1.0, 1.0, 0), Stroke::default()).unwrap();
+        svg.line(&Line::new(0.0, 0.0, f64::INFINITY, 1.0, 0), Stroke::default()).unwrap();
+        let out = String::from_utf8(svg.finish().unwrap()).unwrap();
+
+        assert!(!out.contains("<line") && !out.contains("<g "));
+    }
+
+    struct Failing;
+
+    impl Write for Failing {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_svg_should_report_write_errors() {
+        let lines = vec![Line::new(1.0, 2.0, 3.0, 4.0, 0)];
+        assert!(write_svg(&mut Failing, &lines, 8, 8, None).is_err());
+    }
+
+    #[test]
+    fn write_svg_should_fill_the_background_under_the_lines() {
+        let mut out: Vec<u8> = Vec::new();
+        write_svg(&mut out, &[Line::new(1.0, 2.0, 3.0, 4.0, 0)], 640, 480, Some(Rgb::new(255, 255, 255))).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+
+        let rect = svg.find("<rect width=\"640\" height=\"480\" fill=\"rgb(255,255,255)\" />").unwrap();
+        assert!(rect < svg.find("<line").unwrap());
+    }
+}
@@ -0,0 +1,279 @@
+use std::env;
+use std::io;
+use std::io::prelude::*;
+use output::{Backend, Drawing, Line};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Charset {
+    /* Each character cell holds a 2x4 block of braille dots. */
+    Braille,
+    /* Each character cell holds a single pixel drawn with -, |, / or \. */
+    Ascii,
+}
+
+/// A grid of pixels that lines are rasterized into before being turned into text.
+#[derive(Debug, PartialEq)]
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Option<char>>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Canvas {
+        Canvas{width, height, pixels: vec![None; width * height]}
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x]
+        } else {
+            None
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, stroke: char) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            self.pixels[y as usize * self.width + x as usize] = Some(stroke);
+        }
+    }
+
+    /* Walks the line one pixel at a time along its major axis, after
+       clipping it to the canvas so that only the visible part is walked.
+       Lines with an endpoint that isn't finite, such as a vertex on the
+       camera plane, are left out. */
+    pub fn draw_line(&mut self, line: &Line) {
+        let stroke = ascii_stroke(line.x2 - line.x1, line.y2 - line.y1);
+        let line = match self.clip(line) {
+            Some(line) => line,
+            None => return,
+        };
+
+        let dx = line.x2 - line.x1;
+        let dy = line.y2 - line.y1;
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0);
+
+        for i in 0..(steps as i64 + 1) {
+            let t = i as f64 / steps;
+            let x = (line.x1 + dx * t).floor() as i64;
+            let y = (line.y1 + dy * t).floor() as i64;
+            self.set(x, y, stroke);
+        }
+    }
+
+    /* Liang-Barsky clipping against the canvas rectangle. Returns None when
+       the line misses the canvas or can't be clipped. */
+    fn clip(&self, line: &Line) -> Option<Line> {
+        let dx = line.x2 - line.x1;
+        let dy = line.y2 - line.y1;
+        if ![line.x1, line.y1, dx, dy].iter().all(|v| v.is_finite()) {
+            return None;
+        }
+
+        let (mut t0, mut t1) = (0.0f64, 1.0f64);
+        let boundaries = [
+            (-dx, line.x1),
+            (dx, self.width as f64 - line.x1),
+            (-dy, line.y1),
+            (dy, self.height as f64 - line.y1),
+        ];
+        for &(p, q) in boundaries.iter() {
+            if p == 0.0 {
+                /* Parallel to this boundary, and outside of it. */
+                if q < 0.0 {
+                    return None;
+                }
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
+            }
+        }
+
+        if t0 > t1 {
+            return None;
+        }
+        Some(Line::new(line.x1 + dx * t0, line.y1 + dy * t0, line.x1 + dx * t1, line.y1 + dy * t1, line.triangle))
+    }
+
+    pub fn to_braille(&self) -> Vec<String> {
+        /* Bit for the dot at (column, row) within a braille cell. */
+        const DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+        let cols = self.width.div_ceil(2);
+        let rows = self.height.div_ceil(4);
+        let mut text = Vec::with_capacity(rows);
+
+        for row in 0..rows {
+            let mut line = String::with_capacity(cols * 3);
+            for col in 0..cols {
+                let mut bits = 0;
+                for (dx, column_dots) in DOTS.iter().enumerate() {
+                    for (dy, dot) in column_dots.iter().enumerate() {
+                        if self.get(col * 2 + dx, row * 4 + dy).is_some() {
+                            bits |= *dot;
+                        }
+                    }
+                }
+                line.push(::std::char::from_u32(0x2800 + bits).unwrap());
+            }
+            text.push(line);
+        }
+
+        text
+    }
+
+    pub fn to_ascii(&self) -> Vec<String> {
+        (0..self.height).map(|y| {
+            (0..self.width).map(|x| self.get(x, y).unwrap_or(' ')).collect()
+        }).collect()
+    }
+}
+
+fn ascii_stroke(dx: f64, dy: f64) -> char {
+    if dy.abs() * 2.0 < dx.abs() {
+        '-'
+    } else if dx.abs() * 2.0 < dy.abs() {
+        '|'
+    } else if (dx > 0.0) == (dy > 0.0) {
+        /* Raster y grows downwards, so this slopes down to the right. */
+        '\\'
+    } else {
+        '/'
+    }
+}
+
+/// Returns the pixel dimensions to project into so that a grid of `cols` by
+/// `rows` character cells shows the square canvas without stretching it.
+/// Terminal cells are roughly twice as tall as they are wide.
+pub fn pixel_size(cols: usize, rows: usize, charset: Charset) -> (u32, u32) {
+    match charset {
+        Charset::Braille => {
+            let side = (cols * 2).min(rows * 4);
+            (side as u32, side as u32)
+        },
+        Charset::Ascii => {
+            let width = cols.min(rows * 2);
+            (width as u32, (width / 2) as u32)
+        },
+    }
+}
+
+pub fn write_terminal<W: Write + ?Sized>(out: &mut W, lines: &[Line], image_width: u32, image_height: u32, charset: Charset) -> io::Result<()> {
+    let mut canvas = Canvas::new(image_width as usize, image_height as usize);
+    for line in lines {
+        canvas.draw_line(line);
+    }
+
+    let text = match charset {
+        Charset::Braille => canvas.to_braille(),
+        Charset::Ascii => canvas.to_ascii(),
+    };
+
+    for line in text {
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+/// Size of the terminal attached to stdout in (columns, rows), falling back
+/// to the COLUMNS and LINES environment variables.
+pub fn terminal_size() -> Option<(usize, usize)> {
+    if let Some(size) = terminal_size_from_ioctl() {
+        return Some(size);
+    }
+
+    let cols = env::var("COLUMNS").ok().and_then(|c| c.parse::<usize>().ok());
+    let rows = env::var("LINES").ok().and_then(|r| r.parse::<usize>().ok());
+    match (cols, rows) {
+        (Some(c), Some(r)) if c > 0 && r > 0 => Some((c, r)),
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+fn terminal_size_from_ioctl() -> Option<(usize, usize)> {
+    use libc;
+
+    let mut size = libc::winsize{ws_row: 0, ws_col: 0, ws_xpixel: 0, ws_ypixel: 0};
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    if result == 0 && size.ws_col > 0 && size.ws_row > 0 {
+        Some((size.ws_col as usize, size.ws_row as usize))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn terminal_size_from_ioctl() -> Option<(usize, usize)> {
+    None
+}
+
+pub struct Terminal {
+    pub charset: Charset,
+}
+
+impl Backend for Terminal {
+    fn write(&self, drawing: &Drawing, out: &mut dyn Write) -> io::Result<()> {
+        write_terminal(out, &drawing.lines, drawing.image_width, drawing.image_height, self.charset)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use output::Line;
+    use super::{Canvas, Charset, pixel_size, write_terminal};
+
+    #[test]
+    fn draw_line_should_set_every_pixel_along_the_line() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas.draw_line(&Line::new(0.0, 1.0, 3.0, 1.0, 0));
+        assert_eq!(vec!["    ", "----", "    ", "    "], canvas.to_ascii());
+
+        let mut canvas = Canvas::new(3, 3);
+        canvas.draw_line(&Line::new(0.0, 0.0, 2.0, 2.0, 0));
+        canvas.draw_line(&Line::new(2.0, 0.0, 2.0, 1.0, 0));
+        assert_eq!(vec!["\\ |", " \\|", "  \\"], canvas.to_ascii());
+    }
+
+    #[test]
+    fn draw_line_should_clip_pixels_outside_of_the_canvas() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.draw_line(&Line::new(-5.0, 0.0, 5.0, 0.0, 0));
+        assert_eq!(vec!["--", "  "], canvas.to_ascii());
+    }
+
+    #[test]
+    fn draw_line_should_only_walk_the_visible_part_of_huge_lines() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.draw_line(&Line::new(-1e12, 1.0, 1e12, 1.0, 0));
+        canvas.draw_line(&Line::new(0.0, 0.0, f64::INFINITY, 0.0, 0));
+        canvas.draw_line(&Line::new(f64::NAN, 0.0, 1.0, 0.0, 0));
+        canvas.draw_line(&Line::new(5.0, -4.0, 9.0, -1.0, 0));
+        assert_eq!(vec!["   ", "---"], canvas.to_ascii());
+    }
+
+    #[test]
+    fn to_braille_should_pack_2x4_pixels_per_character() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas.draw_line(&Line::new(0.0, 0.0, 0.0, 3.0, 0));
+        canvas.draw_line(&Line::new(3.0, 3.0, 3.0, 3.0, 0));
+        assert_eq!(vec!["\u{2847}\u{2880}"], canvas.to_braille());
+    }
+
+    #[test]
+    fn pixel_size_should_keep_the_canvas_square() {
+        assert_eq!((96, 96), pixel_size(80, 24, Charset::Braille));
+        assert_eq!((48, 24), pixel_size(80, 24, Charset::Ascii));
+        assert_eq!((40, 20), pixel_size(100, 20, Charset::Ascii));
+    }
+
+    #[test]
+    fn write_terminal_should_print_one_row_per_line() {
+        let mut out: Vec<u8> = Vec::new();
+        write_terminal(&mut out, &[Line::new(0.0, 0.0, 1.0, 0.0, 0)], 2, 2, Charset::Ascii).unwrap();
+        assert_eq!("--\n\n", String::from_utf8(out).unwrap());
+    }
+}
@@ -0,0 +1,123 @@
+use std::io;
+use std::io::prelude::*;
+use output::page::POINTS_PER_MM;
+use output::{Backend, Drawing, Line, polylines};
+use wavefront::material_parser::Material;
+
+const POINTS_PER_CM: f64 = POINTS_PER_MM * 10.0;
+
+/// Writes the lines as a `tikzpicture` that can be `\input` into a LaTeX
+/// document. The picture is `figure_width` points wide and coordinates are
+/// in centimetres. `materials` holds the material of each triangle, indexed
+/// by `Line::triangle`; every distinct material gets a color defined from its
+/// diffuse color, and lines without a material are drawn in black.
+pub fn write_tikz<W: Write + ?Sized>(out: &mut W, lines: &[Line], image_width: u32, image_height: u32, figure_width: f64, stroke_width: f64, materials: &[Option<&Material>]) -> io::Result<()> {
+    let scale = figure_width / POINTS_PER_CM / image_width as f64;
+    let map = |x: f64, y: f64| (x * scale, (image_height as f64 - y) * scale);
+
+    /* Defined colors, named sticks0, sticks1, ... in order of first use. */
+    let mut defined: Vec<&str> = Vec::new();
+    let mut definitions = String::new();
+    let mut color_of = |triangle: usize| -> String {
+        match materials.get(triangle) {
+            Some(&Some(material)) => {
+                let index = match defined.iter().position(|name| *name == material.name) {
+                    Some(index) => index,
+                    None => {
+                        let color = &material.color_diffuse;
+                        definitions.push_str(&format!("\\definecolor{{sticks{}}}{{rgb}}{{{:.3},{:.3},{:.3}}} % {}\n",
+                            defined.len(), color.r.clamp(0.0, 1.0), color.g.clamp(0.0, 1.0), color.b.clamp(0.0, 1.0), material.name));
+                        defined.push(&material.name);
+                        defined.len() - 1
+                    },
+                };
+                format!("sticks{}", index)
+            },
+            _ => "black".to_string(),
+        }
+    };
+
+    let mut draws = String::new();
+    for polyline in polylines(lines) {
+        draws.push_str(&format!("\\draw[{}]", color_of(polyline.triangle)));
+        for (i, &(x, y)) in polyline.points.iter().enumerate() {
+            let (x, y) = map(x, y);
+            if i > 0 {
+                draws.push_str(" --");
+            }
+            draws.push_str(&format!(" ({:.3},{:.3})", x, y));
+        }
+        if polyline.closed {
+            draws.push_str(" -- cycle");
+        }
+        draws.push_str(";\n");
+    }
+
+    writeln!(out, "\\begin{{tikzpicture}}[line width={:.3}pt, line cap=round, line join=round]", stroke_width)?;
+    write!(out, "{}", definitions)?;
+    write!(out, "{}", draws)?;
+    writeln!(out, "\\end{{tikzpicture}}")
+}
+
+pub struct Tikz {
+    pub figure_width: f64,
+    pub stroke_width: f64,
+}
+
+impl Backend for Tikz {
+    fn write(&self, drawing: &Drawing, out: &mut dyn Write) -> io::Result<()> {
+        write_tikz(out, &drawing.lines, drawing.image_width, drawing.image_height, self.figure_width, self.stroke_width, &drawing.triangle_materials())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use output::Line;
+    use output::page::POINTS_PER_MM;
+    use wavefront::material_parser::{Color, Material};
+    use super::write_tikz;
+
+    #[test]
+    fn write_tikz_should_draw_each_polyline_in_centimetres() {
+        let lines = vec![
+            Line::new(0.0, 0.0, 10.0, 0.0, 0),
+            Line::new(10.0, 0.0, 10.0, 5.0, 0),
+            Line::new(10.0, 5.0, 0.0, 0.0, 0),
+        ];
+        let mut out: Vec<u8> = Vec::new();
+        write_tikz(&mut out, &lines, 10, 10, 20.0 * POINTS_PER_MM, 1.0, &[]).unwrap();
+
+        let expected = "\\begin{tikzpicture}[line width=1.000pt, line cap=round, line join=round]
+\\draw[black] (0.000,2.000) -- (2.000,2.000) -- (2.000,1.000) -- cycle;
+\\end{tikzpicture}
+";
+        assert_eq!(expected, String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn write_tikz_should_define_a_color_per_material() {
+        let leaves = Material{name: "Leaves".to_string(), color_diffuse: Color{r: 0.1, g: 0.8, b: 1.5}, ..Default::default()};
+        let bark = Material{name: "Bark".to_string(), color_diffuse: Color{r: 0.5, g: 0.25, b: 0.0}, ..Default::default()};
+
+        let lines = vec![
+            Line::new(0.0, 0.0, 10.0, 10.0, 0),
+            Line::new(0.0, 0.0, 10.0, 10.0, 1),
+            Line::new(0.0, 0.0, 10.0, 10.0, 2),
+            Line::new(0.0, 0.0, 10.0, 10.0, 3),
+        ];
+        let mut out: Vec<u8> = Vec::new();
+        write_tikz(&mut out, &lines, 10, 10, 10.0 * POINTS_PER_MM, 1.0, &[Some(&leaves), Some(&bark), Some(&leaves), None]).unwrap();
+
+        let expected = "\\begin{tikzpicture}[line width=1.000pt, line cap=round, line join=round]
+\\definecolor{sticks0}{rgb}{0.100,0.800,1.000} % Leaves
+\\definecolor{sticks1}{rgb}{0.500,0.250,0.000} % Bark
+\\draw[sticks0] (0.000,1.000) -- (1.000,0.000);
+\\draw[sticks1] (0.000,1.000) -- (1.000,0.000);
+\\draw[sticks0] (0.000,1.000) -- (1.000,0.000);
+\\draw[black] (0.000,1.000) -- (1.000,0.000);
+\\end{tikzpicture}
+";
+        assert_eq!(expected, String::from_utf8(out).unwrap());
+    }
+}
@@ -0,0 +1,273 @@
+use std::io;
+use std::io::prelude::*;
+use camera::Camera;
+use geometry::bvh::Bvh;
+use geometry::intersection::{Ray, Triangle};
+use geometry::vector::Vec3;
+use output::{Backend, Drawing, Line, Rgb, Source};
+use wavefront::material_parser::Material;
+use wavefront::object_parser::Vertex;
+
+/* Hidden line removal tests whether edges are hidden at points about this
+   many pixels apart, and at no more than this many points per edge. */
+const HIDDEN_LINE_STEP: f64 = 2.0;
+const MAX_HIDDEN_LINE_SAMPLES: usize = 1024;
+/* Rays towards the camera start and stop this fraction of their length
+   away from the ends, so the faces an edge lies on don't hide it. */
+const HIDDEN_LINE_EPSILON: f64 = 1e-6;
+
+/// Projects the edges of an object's triangles through a camera onto an
+/// image of `image_width` by `image_height` pixels.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    pub camera: Camera,
+    pub image_width: u32,
+    pub image_height: u32,
+    /// Pixels left blank on every side of the image. The camera's canvas
+    /// is mapped onto what remains.
+    pub margin: u32,
+    pub background: Option<Rgb>,
+    /// Leaves out the parts of edges that faces closer to the camera hide.
+    pub remove_hidden_lines: bool,
+}
+
+impl Renderer {
+    pub fn new(camera: Camera, image_width: u32, image_height: u32) -> Renderer {
+        Renderer{camera, image_width, image_height, margin: 0, background: None, remove_hidden_lines: false}
+    }
+
+    pub fn with_margin(self, margin: u32) -> Renderer {
+        Renderer{margin, ..self}
+    }
+
+    pub fn with_background(self, background: Rgb) -> Renderer {
+        Renderer{background: Some(background), ..self}
+    }
+
+    pub fn with_hidden_lines_removed(self) -> Renderer {
+        Renderer{remove_hidden_lines: true, ..self}
+    }
+
+    /// The size of the image inside the margin, which is what the camera's
+    /// canvas should have the aspect ratio of.
+    pub fn inner_size(&self) -> (u32, u32) {
+        (self.image_width.saturating_sub(self.margin.saturating_mul(2)), self.image_height.saturating_sub(self.margin.saturating_mul(2)))
+    }
+
+    /// Projects the three edges of every triangle, in order, into raster
+    /// space. Takes an `Object` or a `Mesh`. Edges are clipped against the
+    /// camera's near plane, so triangles partly behind the camera give
+    /// shorter or fewer lines. With `remove_hidden_lines`, edges are also
+    /// split where faces in front of them start and stop hiding them.
+    pub fn project<'a, S: Into<Source<'a>>>(&self, source: S) -> Vec<Line> {
+        let source = source.into();
+        let mut lines = Vec::with_capacity(source.triangle_count() * 3);
+        let (inner_width, inner_height) = self.inner_size();
+        let margin = self.margin as f64;
+        let point = |v: &Vertex| Vec3::new(v.x, v.y, v.z);
+        let occluders = if self.remove_hidden_lines {
+            Some(Bvh::new((0..source.triangle_count()).map(|i| {
+                let [v0, v1, v2] = source.corners(i);
+                Triangle::new(point(&v0), point(&v1), point(&v2))
+            }).collect()))
+        } else {
+            None
+        };
+
+        for i in 0..source.triangle_count() {
+            let [v0, v1, v2] = source.corners(i);
+            for &(a, b) in &[(&v0, &v1), (&v1, &v2), (&v2, &v0)] {
+                let (a, b) = (point(a), point(b));
+                let parts = match occluders {
+                    Some(ref occluders) => self.visible_parts(occluders, a, b),
+                    None => vec![(a, b)],
+                };
+                for (a, b) in parts {
+                    if let Some(((x1, y1), (x2, y2))) = self.camera.project_line(&a, &b, inner_width, inner_height) {
+                        lines.push(Line::new(x1 + margin, y1 + margin, x2 + margin, y2 + margin, i));
+                    }
+                }
+            }
+        }
+
+        lines
+    }
+
+    /* Splits the edge from a to b into the runs that no triangle hides,
+       by casting rays towards the camera from points along it. */
+    fn visible_parts(&self, occluders: &Bvh, a: Vec3<f64>, b: Vec3<f64>) -> Vec<(Vec3<f64>, Vec3<f64>)> {
+        let (inner_width, inner_height) = self.inner_size();
+        let samples = match self.camera.project_line(&a, &b, inner_width, inner_height) {
+            Some(((x1, y1), (x2, y2))) => {
+                let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+                if length.is_finite() {
+                    ((length / HIDDEN_LINE_STEP).ceil() as usize).clamp(1, MAX_HIDDEN_LINE_SAMPLES)
+                } else {
+                    MAX_HIDDEN_LINE_SAMPLES
+                }
+            },
+            None => return vec![],
+        };
+
+        let is_visible = |p: Vec3<f64>| {
+            let towards_camera = self.camera.viewpoint(&p) - p;
+            let ray = Ray::new(p + towards_camera * HIDDEN_LINE_EPSILON, towards_camera);
+            !occluders.any_hit(&ray, 1.0 - 2.0 * HIDDEN_LINE_EPSILON)
+        };
+
+        let mut parts = Vec::new();
+        let mut start = None;
+        for k in 0..samples {
+            let visible = is_visible(a.lerp(b, (k as f64 + 0.5) / samples as f64));
+            match (visible, start) {
+                (true, None) => start = Some(k),
+                (false, Some(first)) => {
+                    parts.push((a.lerp(b, first as f64 / samples as f64), a.lerp(b, k as f64 / samples as f64)));
+                    start = None;
+                },
+                _ => (),
+            }
+        }
+        if let Some(first) = start {
+            parts.push((a.lerp(b, first as f64 / samples as f64), b));
+        }
+
+        parts
+    }
+
+    pub fn draw<'a, S: Into<Source<'a>>>(&self, source: S, materials: &'a [Material]) -> Drawing<'a> {
+        let source = source.into();
+        Drawing{
+            lines: self.project(source),
+            image_width: self.image_width,
+            image_height: self.image_height,
+            background: self.background,
+            source,
+            materials,
+        }
+    }
+
+    /// Projects the object or mesh and writes it to `out` using `backend`.
+    pub fn render<'a, S: Into<Source<'a>>>(&self, source: S, materials: &'a [Material], backend: &dyn Backend, out: &mut dyn Write) -> io::Result<()> {
+        backend.write(&self.draw(source, materials), out)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use camera::Camera;
+    use geometry::matrix::Matrix44;
+    use output::Line;
+    use output::svg::Svg;
+    use wavefront::{parse_mesh, parse_object};
+    use super::Renderer;
+
+    fn renderer() -> Renderer {
+        let identity: Matrix44<f64> = Matrix44::new(1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+        Renderer::new(Camera::new(identity, 2.0, 2.0).unwrap(), 100, 100)
+    }
+
+    #[test]
+    fn project_should_return_three_lines_per_triangle() {
+        let object = parse_object("v 0.0 0.0 -1.0
+v 1.0 0.0 -1.0
+v 0.0 1.0 -1.0
+f 1 2 3
+f 3 2 1
+").unwrap();
+
+        assert_eq!(vec![
+            Line::new(50.0, 50.0, 100.0, 50.0, 0),
+            Line::new(100.0, 50.0, 50.0, 0.0, 0),
+            Line::new(50.0, 0.0, 50.0, 50.0, 0),
+            Line::new(50.0, 0.0, 100.0, 50.0, 1),
+            Line::new(100.0, 50.0, 50.0, 50.0, 1),
+            Line::new(50.0, 50.0, 50.0, 0.0, 1),
+        ], renderer().project(&object));
+    }
+
+    #[test]
+    fn project_should_give_the_same_lines_for_a_mesh() {
+        let source = "v 0.0 0.0 -1.0
+v 1.0 0.0 -1.0
+v 0.0 1.0 -1.0
+f 1 2 3
+f 3 2 -1
+";
+        let mesh = parse_mesh(source).unwrap();
+        assert_eq!(renderer().project(&parse_object(source).unwrap()), renderer().project(&mesh));
+    }
+
+    #[test]
+    fn project_should_keep_the_margin_blank() {
+        let object = parse_object("v -1.0 1.0 -1.0
+v 1.0 -1.0 -1.0
+v 0.0 0.0 -1.0
+f 1 2 3
+").unwrap();
+
+        let lines = renderer().with_margin(10).project(&object);
+        assert_eq!(Line::new(10.0, 10.0, 90.0, 90.0, 0), lines[0]);
+
+        assert_eq!((0, 0), renderer().with_margin(u32::MAX / 2 + 1).inner_size());
+    }
+
+    #[test]
+    fn project_should_clip_edges_behind_the_camera() {
+        let object = parse_object("v 0.0 0.0 -2.0
+v 1.0 0.0 -2.0
+v 0.0 0.0 2.0
+f 1 2 3
+").unwrap();
+
+        let lines = renderer().project(&object);
+        assert_eq!(3, lines.len());
+        assert_eq!(Line::new(50.0, 50.0, 75.0, 50.0, 0), lines[0]);
+        assert!(lines.iter().all(|line| [line.x1, line.y1, line.x2, line.y2].iter().all(|&c| (0.0..=100.0).contains(&c))));
+
+        let behind = parse_object("v 0.0 0.0 2.0
+v 1.0 0.0 2.0
+v 0.0 1.0 2.0
+f 1 2 3
+").unwrap();
+        assert!(renderer().project(&behind).is_empty());
+    }
+
+    #[test]
+    fn project_should_split_edges_behind_faces_when_removing_hidden_lines() {
+        /* The small triangle in front hides the middle of the bottom edge of
+           the large one, from x = 37.5 to 62.5 in raster space. */
+        let object = parse_object("v -1.0 -1.0 -2.0
+v 1.0 -1.0 -2.0
+v 0.0 1.0 -2.0
+v -8.0 0.0 -4.0
+v 8.0 0.0 -4.0
+v 0.0 8.0 -4.0
+f 1 2 3
+f 4 5 6
+").unwrap();
+
+        assert_eq!(6, renderer().project(&object).len());
+
+        let lines = renderer().with_hidden_lines_removed().project(&object);
+        let bottom: Vec<&Line> = lines.iter().filter(|line| line.triangle == 1 && line.y1 == 50.0 && line.y2 == 50.0).collect();
+        assert_eq!(2, bottom.len());
+        assert!((bottom[0].x1 - -50.0).abs() < 1e-9 && (bottom[0].x2 - 37.5).abs() <= 2.0);
+        assert!((bottom[1].x1 - 62.5).abs() <= 2.0 && (bottom[1].x2 - 150.0).abs() < 1e-9);
+        assert_eq!(3, lines.iter().filter(|line| line.triangle == 0).count());
+    }
+
+    #[test]
+    fn render_should_write_the_drawing_with_the_backend() {
+        let object = parse_object("v 0.0 0.0 -1.0
+f 1 1 1
+").unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        renderer().render(&object, &[], &Svg, &mut out).unwrap();
+
+        let svg = String::from_utf8(out).unwrap();
+        assert!(svg.contains("width=\"100\" height=\"100\""));
+        assert_eq!(3, svg.matches("<line x1=\"50.000\" y1=\"50.000\" x2=\"50.000\" y2=\"50.000\"").count());
+    }
+}
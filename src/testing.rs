@@ -0,0 +1,7 @@
+/* Assertions shared by the unit tests. */
+
+macro_rules! assert_delta {
+    ($x:expr, $y:expr, $d:expr) => {
+        if $x - $y >= $d || $y - $x >= $d { panic!("x: {:?}, y: {:?}, d: {:?}", $x, $y, $d); }
+    }
+}
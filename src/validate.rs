@@ -0,0 +1,355 @@
+use std::fmt;
+use geometry::intersection::Triangle;
+use geometry::vector::Vec3;
+use mesh::{HalfEdgeMesh, Mesh};
+use wavefront::material_parser::Material;
+use wavefront::object_parser::{MeshSource, Vertex, parse_mesh_source};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found in an object file. `check` names the kind of problem, as
+/// a short identifier that scripts can match on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub check: &'static str,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn error(check: &'static str, line: Option<usize>, message: String) -> Issue {
+        Issue{severity: Severity::Error, check, line, message}
+    }
+
+    pub fn warning(check: &'static str, line: Option<usize>, message: String) -> Issue {
+        Issue{severity: Severity::Warning, check, line, message}
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        write!(f, "{} [{}]: {}", self.severity, self.check, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    pub fn error_count(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Error).count()
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Warning).count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    /// Sorts the issues by line, with issues that have no line first.
+    pub fn sort(&mut self) {
+        self.issues.sort_by_key(|issue| issue.line);
+    }
+
+    pub fn to_json(&self) -> String {
+        let issues: Vec<String> = self.issues.iter().map(|issue| {
+            format!("{{\"severity\":\"{}\",\"check\":\"{}\",\"line\":{},\"message\":{}}}",
+                    issue.severity,
+                    issue.check,
+                    issue.line.map_or("null".to_string(), |line| line.to_string()),
+                    json_string(&issue.message))
+        }).collect();
+
+        format!("{{\"valid\":{},\"errors\":{},\"warnings\":{},\"issues\":[{}]}}",
+                !self.has_errors(), self.error_count(), self.warning_count(), issues.join(","))
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        write!(f, "{} errors, {} warnings", self.error_count(), self.warning_count())
+    }
+}
+
+/// Checks an object file for problems that would trip up renderers and mesh
+/// processing. Material names are only checked when `materials` is given.
+pub fn validate_object(source: &str, materials: Option<&[Material]>) -> Report {
+    match parse_mesh_source(source) {
+        Ok(parsed) => validate_source(&parsed, materials),
+        Err(why) => Report{issues: vec![Issue::error("parse-error", None, why)]},
+    }
+}
+
+/// Like `validate_object`, for an object file that has already been parsed.
+pub fn validate_source(parsed: &MeshSource, materials: Option<&[Material]>) -> Report {
+    let mut report = Report::default();
+
+    for &line in &parsed.invalid_face_lines {
+        report.issues.push(Issue::error("invalid-index", Some(line), "face refers to a vertex that doesn't exist".to_string()));
+    }
+
+    check_vertices(parsed, &mut report);
+    check_faces(parsed, &mut report);
+    check_edges(parsed, &mut report);
+    if let Some(materials) = materials {
+        check_materials(parsed, materials, &mut report);
+    }
+
+    report.sort();
+    report
+}
+
+fn check_vertices(parsed: &MeshSource, report: &mut Report) {
+    let mesh = &parsed.mesh;
+    let is_finite = |v: &Vertex| v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+
+    for (i, position) in mesh.positions.iter().enumerate().filter(|&(_, p)| !is_finite(p)) {
+        report.issues.push(Issue::error("non-finite", parsed.position_lines.get(i).cloned(),
+                                        format!("vertex {} has a non-finite coordinate: {:?}", i + 1, position)));
+    }
+    for (i, normal) in mesh.normals.iter().enumerate().filter(|&(_, n)| !is_finite(n)) {
+        report.issues.push(Issue::error("non-finite", parsed.normal_lines.get(i).cloned(),
+                                        format!("normal {} has a non-finite coordinate: {:?}", i + 1, normal)));
+    }
+
+    let mut used = vec![false; mesh.positions.len()];
+    for face in &mesh.faces {
+        for &v in &face.v {
+            used[v] = true;
+        }
+    }
+    for (i, _) in used.iter().enumerate().filter(|&(_, &used)| !used) {
+        report.issues.push(Issue::warning("unused-vertex", parsed.position_lines.get(i).cloned(),
+                                          format!("vertex {} isn't used by any face", i + 1)));
+    }
+}
+
+fn check_faces(parsed: &MeshSource, report: &mut Report) {
+    let mesh = &parsed.mesh;
+    let point = |v: usize| {
+        let p = mesh.positions[v];
+        Vec3::new(p.x, p.y, p.z)
+    };
+
+    for (i, face) in mesh.faces.iter().enumerate() {
+        let triangle = Triangle::new(point(face.v[0]), point(face.v[1]), point(face.v[2]));
+
+        // Relative to the longest edge, so that the check doesn't depend on
+        // the scale of the model.
+        let longest = [(triangle.a, triangle.b), (triangle.b, triangle.c), (triangle.c, triangle.a)].iter()
+            .map(|&(p, q)| (q - p).length_squared())
+            .fold(0.0, f64::max);
+        if triangle.area() <= longest * f64::EPSILON {
+            report.issues.push(Issue::warning("degenerate-triangle", parsed.face_lines.get(i).cloned(),
+                                              "triangle has no area".to_string()));
+        }
+    }
+}
+
+fn check_edges(parsed: &MeshSource, report: &mut Report) {
+    // Faces with a repeated corner are already reported as degenerate, and
+    // would make their edges look non-manifold.
+    let kept: Vec<usize> = (0..parsed.mesh.faces.len()).filter(|&f| {
+        let [a, b, c] = parsed.mesh.faces[f].v;
+        a != b && b != c && c != a
+    }).collect();
+    let mesh = Mesh{faces: kept.iter().map(|&f| parsed.mesh.faces[f]).collect(), ..parsed.mesh.clone()};
+    let half_edges = HalfEdgeMesh::new(&mesh);
+
+    for e in 0..half_edges.edge_count() {
+        let (a, b) = half_edges.edge_vertices(e);
+        let lines: Vec<usize> = half_edges.edge_faces(e).iter().filter_map(|&f| parsed.face_lines.get(kept[f]).cloned()).collect();
+        let on_lines = lines.iter().map(|line| line.to_string()).collect::<Vec<_>>().join(", ");
+
+        if half_edges.is_non_manifold_edge(e) {
+            report.issues.push(Issue::error("non-manifold-edge", lines.first().cloned(),
+                                            format!("edge between vertices {} and {} is shared by {} faces, on lines {}", a + 1, b + 1, lines.len(), on_lines)));
+        } else if half_edges.is_misoriented_edge(e) {
+            report.issues.push(Issue::error("inconsistent-winding", lines.first().cloned(),
+                                            format!("faces on lines {} have inconsistent winding across the edge between vertices {} and {}", on_lines, a + 1, b + 1)));
+        }
+    }
+}
+
+fn check_materials(parsed: &MeshSource, materials: &[Material], report: &mut Report) {
+    let mesh = &parsed.mesh;
+
+    for (id, name) in mesh.materials.iter().enumerate() {
+        if materials.iter().any(|material| &material.name == name) {
+            continue;
+        }
+
+        let first_use = mesh.faces.iter().position(|face| face.material == Some(id));
+        report.issues.push(Issue::error("missing-material", first_use.and_then(|f| parsed.face_lines.get(f).cloned()),
+                                        format!("material {} isn't defined", name)));
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len() + 2);
+    escaped.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests
+{
+    use wavefront::material_parser::Material;
+    use super::{Issue, Report, Severity, json_string, validate_object};
+
+    fn checks(report: &Report) -> Vec<(&'static str, Option<usize>)> {
+        report.issues.iter().map(|issue| (issue.check, issue.line)).collect()
+    }
+
+    const TETRAHEDRON: &str = "# A closed tetrahedron
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 0.0 0.0 1.0
+usemtl Red
+f 1 3 2
+f 1 2 4
+f 2 3 4
+f 3 1 4
+";
+
+    #[test]
+    fn valid_object_should_have_no_issues() {
+        let red = Material{name: "Red".to_string(), ..Default::default()};
+        let report = validate_object(TETRAHEDRON, Some(&[red]));
+
+        assert_eq!(Report::default(), report);
+        assert_eq!("0 errors, 0 warnings", report.to_string());
+    }
+
+    #[test]
+    fn validate_should_report_missing_materials() {
+        let report = validate_object(TETRAHEDRON, Some(&[]));
+
+        assert_eq!(vec![("missing-material", Some(7))], checks(&report));
+        assert!(validate_object(TETRAHEDRON, None).issues.is_empty());
+    }
+
+    #[test]
+    fn validate_should_report_invalid_indices_with_their_lines() {
+        let report = validate_object("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+f 1 2 3
+
+f 1 2 4
+f 1//1 2 3
+", None);
+
+        assert_eq!(vec![("invalid-index", Some(6)), ("invalid-index", Some(7))], checks(&report));
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn validate_should_warn_about_degenerate_triangles_and_unused_vertices() {
+        let report = validate_object("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 2.0 0.0 0.0
+v 5.0 5.0 5.0
+f 1 2 3
+f 1 1 2
+", None);
+
+        assert_eq!(vec![("unused-vertex", Some(4)), ("degenerate-triangle", Some(5)), ("degenerate-triangle", Some(6))], checks(&report));
+        assert!(!report.has_errors());
+        assert_eq!(3, report.warning_count());
+    }
+
+    #[test]
+    fn validate_should_report_non_manifold_edges_and_winding() {
+        let report = validate_object("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+v 0.0 -1.0 0.0
+v 0.0 0.0 1.0
+v 1.0 1.0 0.0
+f 1 2 3
+f 2 1 4
+f 1 2 5
+f 2 3 6
+", None);
+
+        assert_eq!(vec![("non-manifold-edge", Some(7)), ("inconsistent-winding", Some(7))], checks(&report));
+        assert_eq!("faces on lines 7, 10 have inconsistent winding across the edge between vertices 2 and 3", report.issues[1].message);
+    }
+
+    #[test]
+    fn validate_should_find_the_lines_of_unspaced_keywords() {
+        let report = validate_object("v 0.0 0.0 0.0\nv1.0 0.0 0.0\u{2028}v 0.0 1.0 0.0\nv 5.0 5.0 5.0\nf1 2 3\n# done\nf1 1 2\n", None);
+
+        assert_eq!(vec![("unused-vertex", Some(4)), ("degenerate-triangle", Some(7))], checks(&report));
+    }
+
+    #[test]
+    fn validate_should_report_parse_errors_with_their_line() {
+        let report = validate_object("v 0.0 0.0 0.0
+f 1 1 1 1
+", None);
+
+        assert_eq!(vec![("parse-error", None)], checks(&report));
+        assert!(report.issues[0].message.starts_with("Parser error on line 2:"));
+    }
+
+    #[test]
+    fn validate_should_report_non_finite_coordinates() {
+        let huge = format!("v {}.0 0.0 0.0\nvn 0.0 0.0 1.0\n", "9".repeat(400));
+        let report = validate_object(&huge, None);
+
+        assert_eq!(vec![("non-finite", Some(1)), ("unused-vertex", Some(1))], checks(&report));
+    }
+
+    #[test]
+    fn report_should_serialize_to_json() {
+        let report = Report{issues: vec![
+            Issue::error("parse-error", None, "bad \"line\"\n".to_string()),
+            Issue::warning("unused-vertex", Some(3), "vertex 3 isn't used by any face".to_string()),
+        ]};
+
+        assert_eq!(Severity::Error, report.issues[0].severity);
+        assert_eq!("{\"valid\":false,\"errors\":1,\"warnings\":1,\"issues\":[\
+{\"severity\":\"error\",\"check\":\"parse-error\",\"line\":null,\"message\":\"bad \\\"line\\\"\\n\"},\
+{\"severity\":\"warning\",\"check\":\"unused-vertex\",\"line\":3,\"message\":\"vertex 3 isn't used by any face\"}]}", report.to_json());
+        assert_eq!("\"\\u0001\"", json_string("\u{1}"));
+    }
+}
@@ -35,9 +35,9 @@ impl Default for Material {
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Color {
-    r: f64,
-    g: f64,
-    b: f64
+    pub r: f64,
+    pub g: f64,
+    pub b: f64
 }
 
 impl Default for Color {
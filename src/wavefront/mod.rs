@@ -1,3 +1,8 @@
 pub mod material_parser;
 pub mod object_parser;
+pub mod object_writer;
 mod parser_utilities;
+
+pub use self::material_parser::parse_materials;
+pub use self::object_parser::{parse_mesh, parse_mesh_source, parse_object};
+pub use self::object_writer::write_mesh;
@@ -1,6 +1,8 @@
 use nom::{space};
 use nom::IResult::*;
 use wavefront::parser_utilities::{parse_f64, parse_int, parse_ignored_line, not_space};
+use mesh::{Face, Mesh};
+use std::collections::{HashMap, HashSet};
 use std::str;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -28,6 +30,8 @@ pub struct Triangle {
     pub vn2: Option<Vertex>,
     pub vn3: Option<Vertex>,
     pub materialName: String,
+    /// Index into `Object::group_lists`, like `Face::groups`.
+    pub groups: Option<usize>,
 }
 
 impl Default for Triangle {
@@ -43,6 +47,7 @@ impl Default for Triangle {
             vn2: None,
             vn3: None,
             materialName: "".to_string(),
+            groups: None,
         }
     }
 }
@@ -101,6 +106,9 @@ pub struct Object {
     pub raw_vertices: Vec<Vertex>,
     pub raw_vertices_texture: Vec<Vertex>,
     pub raw_vertices_normals: Vec<Vertex>,
+    /// Each distinct list of groups named by a `g` line, shared by the
+    /// triangles that follow it.
+    pub group_lists: Vec<Vec<String>>,
 }
 
 impl Default for Object {
@@ -110,15 +118,112 @@ impl Default for Object {
             raw_vertices: vec![],
             raw_vertices_texture: vec![],
             raw_vertices_normals: vec![],
+            group_lists: vec![],
         }
     }
 }
 
+impl Object {
+    pub fn groups(&self, triangle: &Triangle) -> &[String] {
+        triangle.groups.map_or(&[], |id| &self.group_lists[id])
+    }
+}
+
 pub fn parse_object(string: &str) -> Result<Object, String> {
+    construct_object_struct(&parse_all_values(string)?)
+}
+
+/// Parses an object file into an indexed `Mesh`, where faces refer to shared
+/// vertices instead of holding copies of them.
+pub fn parse_mesh(string: &str) -> Result<Mesh, String> {
+    construct_mesh_struct(&parse_all_values(string)?, None)
+}
+
+/// An object file read by `parse_mesh_source`, with the line numbers (from
+/// 1) that its parts came from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshSource {
+    pub mesh: Mesh,
+    pub position_lines: Vec<usize>,
+    pub normal_lines: Vec<usize>,
+    pub face_lines: Vec<usize>,
+    /// Lines of the faces left out of `mesh` because one of their indices
+    /// is out of range.
+    pub invalid_face_lines: Vec<usize>,
+}
+
+/// Parses an object file like `parse_mesh`, but keeps going past faces with
+/// invalid indices and remembers where everything was, for reporting
+/// problems with the file.
+pub fn parse_mesh_source(string: &str) -> Result<MeshSource, String> {
+    let (values, lines): (Vec<Value>, Vec<usize>) = match parse_values_with_lines(string.as_bytes()) {
+        Ok(parsed) => parsed.into_iter().unzip(),
+        Err((line, remaining)) => {
+            let text = remaining.split(|&b| b == b'\n').next().unwrap_or(b"");
+            return Err(format!("Parser error on line {}: {:?}", line, String::from_utf8_lossy(text).trim_end_matches('\r')));
+        }
+    };
+
+    let mut invalid_faces = Vec::new();
+    let mesh = construct_mesh_struct(&values, Some(&mut invalid_faces))?;
+    let invalid_faces: HashSet<usize> = invalid_faces.into_iter().collect();
+
+    let lines_of = |is_kind: fn(&Value) -> bool| -> Vec<usize> {
+        values.iter().zip(&lines).filter(|&(value, _)| is_kind(value)).map(|(_, &line)| line).collect()
+    };
+    let all_face_lines = lines_of(|value| matches!(*value, Value::ValueTriangle(_)));
+    let (invalid, valid): (Vec<_>, Vec<_>) = all_face_lines.into_iter().enumerate()
+        .partition(|&(i, _)| invalid_faces.contains(&i));
+
+    Ok(MeshSource{
+        mesh,
+        position_lines: lines_of(|value| matches!(*value, Value::ValueVertexGeometric(_))),
+        normal_lines: lines_of(|value| matches!(*value, Value::ValueVertexNormal(_))),
+        face_lines: valid.into_iter().map(|(_, line)| line).collect(),
+        invalid_face_lines: invalid.into_iter().map(|(_, line)| line).collect(),
+    })
+}
+
+/* The line the parser got stuck on and the input left from there. */
+type StuckAt<'a> = (usize, &'a [u8]);
+
+/* Parses the same values as `parse_values`, one at a time, with the line
+   (from 1) each one is on. Every value and every ignored line ends in
+   exactly one line end, so counting them follows the grammar's idea of a
+   line. */
+fn parse_values_with_lines(input: &[u8]) -> Result<Vec<(Value, usize)>, StuckAt<'_>> {
+    let mut values = Vec::new();
+    let mut line = 1;
+    let mut remaining = input;
+
+    loop {
+        while let Done(rest, _) = parse_ignored_line(remaining) {
+            if rest.len() == remaining.len() {
+                break;
+            }
+            remaining = rest;
+            line += 1;
+        }
+        if remaining.is_empty() {
+            return Ok(values);
+        }
+
+        match parse_value(remaining) {
+            Done(rest, value) => {
+                values.push((value, line));
+                remaining = rest;
+                line += 1;
+            }
+            _ => return Err((line, remaining)),
+        }
+    }
+}
+
+fn parse_all_values(string: &str) -> Result<Vec<Value>, String> {
     let result = parse_values(string.as_bytes());
     if let Done(remaining, parsed) = result {
         if remaining == [] {
-            construct_object_struct(&parsed)
+            Ok(parsed)
         } else {
             Err(format!("Parser error: Failed parsing everything. Leftover: {:?}", remaining))
         }
@@ -134,12 +239,25 @@ pub fn parse_object(string: &str) -> Result<Object, String> {
 fn construct_object_struct(values: &Vec<Value>) -> Result<Object, String> {
     let mut object: Object = Default::default();
     let mut materialName: String = "".to_string();
+    let mut group_ids: HashMap<&[String], usize> = HashMap::new();
+    let mut groups: Option<usize> = None;
 
     'parsing_values: for value in values {
         match value {
             &Value::ValueUseMaterialName(ref value_name) => {
                 materialName = value_name.clone();
             }
+            &Value::ValueGroup(ref value_groups) if is_default_group(value_groups) => {
+                groups = None;
+            }
+            &Value::ValueGroup(ref value_groups) => {
+                let next_id = object.group_lists.len();
+                let id = *group_ids.entry(value_groups).or_insert(next_id);
+                if id == next_id {
+                    object.group_lists.push(value_groups.clone());
+                }
+                groups = Some(id);
+            }
             &Value::ValueVertexGeometric(ref value_vertex) => {
                 object.raw_vertices.push(*value_vertex);
             }
@@ -151,6 +269,7 @@ fn construct_object_struct(values: &Vec<Value>) -> Result<Object, String> {
             }
             &Value::ValueTriangle(ref triangle_of_triplets) => {
                 let mut triangle: Triangle = try!(construct_triangle_from_triangle_of_triplets(&object, triangle_of_triplets, &materialName));
+                triangle.groups = groups;
                 object.triangles.push(triangle);
             }
             _ => {
@@ -194,8 +313,89 @@ fn construct_triangle_from_triangle_of_triplets(object: &Object, triangle_of_tri
     Ok(triangle)
 }
 
+/* With `invalid_faces`, faces with an index out of range are left out and
+   their position among all faces is pushed to it, instead of failing. */
+fn construct_mesh_struct(values: &[Value], mut invalid_faces: Option<&mut Vec<usize>>) -> Result<Mesh, String> {
+    let mut mesh: Mesh = Default::default();
+    let mut material_ids: HashMap<&str, usize> = HashMap::new();
+    let mut group_ids: HashMap<&[String], usize> = HashMap::new();
+    let mut material: Option<usize> = None;
+    let mut groups: Option<usize> = None;
+    let mut face_count = 0;
+
+    for value in values {
+        match *value {
+            Value::ValueMaterialLibraryName(ref name) => mesh.material_libraries.push(name.clone()),
+            Value::ValueUseMaterialName(ref value_name) if value_name.is_empty() => material = None,
+            Value::ValueUseMaterialName(ref value_name) => {
+                let next_id = mesh.materials.len();
+                let id = *material_ids.entry(value_name).or_insert(next_id);
+                if id == next_id {
+                    mesh.materials.push(value_name.clone());
+                }
+                material = Some(id);
+            }
+            Value::ValueGroup(ref value_groups) if is_default_group(value_groups) => groups = None,
+            Value::ValueGroup(ref value_groups) => {
+                let next_id = mesh.group_lists.len();
+                let id = *group_ids.entry(value_groups).or_insert(next_id);
+                if id == next_id {
+                    mesh.group_lists.push(value_groups.clone());
+                }
+                groups = Some(id);
+            }
+            Value::ValueVertexGeometric(value_vertex) => mesh.positions.push(value_vertex),
+            Value::ValueVertexTexture(value_vertex) => mesh.texcoords.push(value_vertex),
+            Value::ValueVertexNormal(value_vertex) => mesh.normals.push(value_vertex),
+            Value::ValueTriangle(ref triangle_of_triplets) => {
+                match construct_face(&mesh, triangle_of_triplets, material, groups) {
+                    Ok(face) => mesh.faces.push(face),
+                    Err(why) => match invalid_faces {
+                        Some(ref mut invalid_faces) => invalid_faces.push(face_count),
+                        None => return Err(why),
+                    },
+                }
+                face_count += 1;
+            }
+            _ => {
+                // Nothing
+            }
+        }
+    }
+
+    Ok(mesh)
+}
+
+/* `g default` goes back to the group faces are in before any `g`. */
+fn is_default_group(names: &[String]) -> bool {
+    names.len() == 1 && names[0] == "default"
+}
+
+fn construct_face(mesh: &Mesh, triangle_of_triplets: &TriangleOfVertexTriplets, material: Option<usize>, groups: Option<usize>) -> Result<Face, String> {
+    let triplets = [&triangle_of_triplets.v1, &triangle_of_triplets.v2, &triangle_of_triplets.v3];
+    let mut face = Face{v: [0; 3], vt: [None; 3], vn: [None; 3], material, groups};
+
+    for (corner, triplet) in triplets.iter().enumerate() {
+        face.v[corner] = resolve_index(mesh.positions.len(), triplet.v)?;
+        if let Some(vt) = triplet.vt {
+            face.vt[corner] = Some(resolve_index(mesh.texcoords.len(), vt)?);
+        }
+        if let Some(vn) = triplet.vn {
+            face.vn[corner] = Some(resolve_index(mesh.normals.len(), vn)?);
+        }
+    }
+
+    Ok(face)
+}
+
 fn get_indexed_vertex(list: &Vec<Vertex>, index: isize) -> Result<Vertex, String> {
-    let num_vertices = list.len() as isize;
+    Ok(list[resolve_index(list.len(), index)?])
+}
+
+/* Object files count from 1, and negative indices count back from the
+   latest vertex. */
+fn resolve_index(len: usize, index: isize) -> Result<usize, String> {
+    let num_vertices = len as isize;
     let mut usize_index = 0;
 
     if index > 0 && index <= num_vertices {
@@ -206,22 +406,14 @@ fn get_indexed_vertex(list: &Vec<Vertex>, index: isize) -> Result<Vertex, String
         return Err("Invalid index".to_string());
     }
 
-    Ok(list[usize_index])
+    Ok(usize_index)
 }
 
 named!(parse_values<Vec<Value> >,
     many0!(
         chain!(
             many0!(parse_ignored_line) ~
-            value: alt!(
-                parse_material_library_name_value |
-                parse_use_material_value |
-                parse_vertex_geometry_value |
-                parse_vertex_normal_value |
-                parse_triangle_value |
-                parse_ValueGroup_value |
-                parse_smoothing_ValueGroup_value
-            ) ~
+            value: parse_value ~
             many0!(parse_ignored_line),
 
             ||{value}
@@ -229,6 +421,18 @@ named!(parse_values<Vec<Value> >,
     )
 );
 
+named!(parse_value<Value>,
+    alt!(
+        parse_material_library_name_value |
+        parse_use_material_value |
+        parse_vertex_geometry_value |
+        parse_vertex_normal_value |
+        parse_triangle_value |
+        parse_ValueGroup_value |
+        parse_smoothing_ValueGroup_value
+    )
+);
+
 named!(parse_material_library_name_value<Value>,
     chain!(
         name: parse_material_library_name,
@@ -262,10 +466,10 @@ named!(parse_use_material_name<String>,
         many0!(space) ~
         tag!("usemtl") ~
         many0!(space) ~
-        name: map_res!(not_space, str::from_utf8) ~
+        name: opt!(map_res!(not_space, str::from_utf8)) ~
         parse_ignored_line,
 
-        ||{name.to_string()}
+        ||{name.unwrap_or("").to_string()}
     )
 );
 
@@ -472,7 +676,8 @@ f 1// 1// 1//
                     vn1: None,
                     vn2: None,
                     vn3: None,
-                    materialName: "".to_string()
+                    materialName: "".to_string(),
+                    groups: None
                 }
             ],
             raw_vertices: vec![
@@ -480,6 +685,7 @@ f 1// 1// 1//
             ],
             raw_vertices_texture: vec![],
             raw_vertices_normals: vec![],
+            group_lists: vec![],
         };
 
         assert_eq!(Ok(expected_object), parse_object(test_case));
@@ -598,7 +804,8 @@ l
                     vn1: Some(Vertex{x: 10.2, y: 10.2, z: -10.2}),
                     vn2: Some(Vertex{x: 6.2, y: 6.2, z: -6.2}),
                     vn3: Some(Vertex{x: 4.2, y: 4.2, z: -4.2}),
-                    materialName: "Material.01".to_string()
+                    materialName: "Material.01".to_string(),
+                    groups: Some(0)
                 },
                 Triangle{
                     v1: Vertex{x: -1.0, y: 1.0, z: 1.0},
@@ -610,7 +817,8 @@ l
                     vn1: None,
                     vn2: None,
                     vn3: None,
-                    materialName: "Material.02".to_string()
+                    materialName: "Material.02".to_string(),
+                    groups: Some(0)
                 }
             ],
             raw_vertices: vec![
@@ -652,6 +860,7 @@ l
                 Vertex{x: 10.2, y: 10.2, z: -10.2},
                 Vertex{x: 11.2, y: 11.2, z: -11.2}
             ],
+            group_lists: vec![vec!["ValueGroup.2".to_string()]],
         };
 
         assert_eq!(Ok(expected_object), construct_object_struct(&values));
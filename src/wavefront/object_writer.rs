@@ -0,0 +1,128 @@
+use std::io;
+use std::io::prelude::*;
+use mesh::Mesh;
+
+/// Writes a mesh as an object file. Groups and materials are only written
+/// when they change from one face to the next, and the mesh's `mtllib`
+/// files are written at the top. Faces without groups or a material after
+/// ones with them get `g default` or a `usemtl` without a name.
+pub fn write_mesh(mesh: &Mesh, out: &mut dyn Write) -> io::Result<()> {
+    for library in &mesh.material_libraries {
+        writeln!(out, "mtllib {}", library)?;
+    }
+
+    for v in &mesh.positions {
+        writeln!(out, "v {} {} {}", v.x, v.y, v.z)?;
+    }
+    for vt in &mesh.texcoords {
+        writeln!(out, "vt {} {} {}", vt.x, vt.y, vt.z)?;
+    }
+    for vn in &mesh.normals {
+        writeln!(out, "vn {} {} {}", vn.x, vn.y, vn.z)?;
+    }
+
+    let mut material = None;
+    let mut groups = None;
+    for face in &mesh.faces {
+        if face.groups != groups {
+            match face.groups {
+                Some(_) => writeln!(out, "g {}", mesh.groups(face).join(" "))?,
+                None => writeln!(out, "g default")?,
+            }
+            groups = face.groups;
+        }
+        if face.material != material {
+            match face.material {
+                Some(_) => writeln!(out, "usemtl {}", mesh.material_name(face))?,
+                None => writeln!(out, "usemtl")?,
+            }
+            material = face.material;
+        }
+
+        write!(out, "f")?;
+        for corner in 0..3 {
+            write!(out, " {}", face.v[corner] + 1)?;
+            match (face.vt[corner], face.vn[corner]) {
+                (Some(vt), Some(vn)) => write!(out, "/{}/{}", vt + 1, vn + 1)?,
+                (Some(vt), None) => write!(out, "/{}", vt + 1)?,
+                (None, Some(vn)) => write!(out, "//{}", vn + 1)?,
+                (None, None) => (),
+            }
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::str;
+    use wavefront::parse_mesh;
+    use super::write_mesh;
+
+    #[test]
+    fn written_mesh_should_parse_back_the_same() {
+        let mesh = parse_mesh("mtllib scene.mtl
+v 0.0 0.0 0.0
+v 1.5 0.0 0.0
+v 0.0 -1.0 0.25
+vn 0.0 0.0 1.0
+f 1 2 3
+g body
+usemtl Red
+f 1//1 2//1 3//1
+usemtl Blue
+f 3 2 1
+").unwrap();
+
+        let mut output: Vec<u8> = Vec::new();
+        write_mesh(&mesh, &mut output).unwrap();
+
+        let written = str::from_utf8(&output).unwrap();
+        assert_eq!("mtllib scene.mtl
+v 0 0 0
+v 1.5 0 0
+v 0 -1 0.25
+vn 0 0 1
+f 1 2 3
+g body
+usemtl Red
+f 1//1 2//1 3//1
+usemtl Blue
+f 3 2 1
+", written);
+        assert_eq!(mesh, parse_mesh(written).unwrap());
+    }
+
+    #[test]
+    fn faces_leaving_groups_and_materials_should_parse_back_without_them() {
+        let mesh = parse_mesh("v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 0.0 1.0 0.0
+g body
+usemtl Red
+f 1 2 3
+g default
+f 1 2 3
+usemtl
+f 3 2 1
+").unwrap();
+        assert_eq!((None, None), (mesh.faces[2].groups, mesh.faces[2].material));
+
+        let mut output: Vec<u8> = Vec::new();
+        write_mesh(&mesh, &mut output).unwrap();
+
+        let written = str::from_utf8(&output).unwrap();
+        assert!(written.ends_with("g body
+usemtl Red
+f 1 2 3
+g default
+f 1 2 3
+usemtl
+f 3 2 1
+"));
+        assert_eq!(mesh, parse_mesh(written).unwrap());
+    }
+}